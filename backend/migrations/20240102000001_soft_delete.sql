@@ -0,0 +1,10 @@
+-- Soft deletion for projects and canvas nodes
+-- Trashed rows keep deleted_at set until the purge task removes them
+
+ALTER TABLE projects     ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_projects_deleted_at
+    ON projects(deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_canvas_nodes_deleted_at
+    ON canvas_nodes(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,19 @@
+-- Full-text search over projects and canvas node content
+-- Node bodies are truncated before indexing to stay under the tsvector size limit
+
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english'::regconfig, coalesce(name, '')), 'A') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(description, '')), 'B')
+    ) STORED;
+
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS search_vector tsvector
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english'::regconfig, coalesce(title, '')), 'A') ||
+        setweight(to_tsvector('english'::regconfig, coalesce(description, '')), 'B') ||
+        setweight(to_tsvector('english'::regconfig, left(coalesce(content, ''), 200000)), 'C') ||
+        setweight(to_tsvector('english'::regconfig, left(coalesce(generated_code, ''), 200000)), 'D')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_projects_search     ON projects     USING GIN (search_vector);
+CREATE INDEX IF NOT EXISTS idx_canvas_nodes_search ON canvas_nodes USING GIN (search_vector);
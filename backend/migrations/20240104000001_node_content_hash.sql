@@ -0,0 +1,6 @@
+-- Hash of a node's heavy bodies so clients can skip re-downloading unchanged content
+
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS content_hash TEXT NOT NULL
+    GENERATED ALWAYS AS (
+        md5(coalesce(content, '') || E'\x1f' || coalesce(generated_code, ''))
+    ) STORED;
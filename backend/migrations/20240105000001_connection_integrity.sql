@@ -0,0 +1,24 @@
+-- Connections must reference existing nodes and disappear with them
+-- Orphaned rows left by earlier versions are dropped so the constraints can be added
+
+DELETE FROM node_connections c
+WHERE NOT EXISTS (
+        SELECT 1 FROM canvas_nodes n
+        WHERE n.project_id = c.project_id AND n.client_id = c.from_client_id
+      )
+   OR NOT EXISTS (
+        SELECT 1 FROM canvas_nodes n
+        WHERE n.project_id = c.project_id AND n.client_id = c.to_client_id
+      );
+
+ALTER TABLE node_connections
+    ADD CONSTRAINT node_connections_from_node_fkey
+    FOREIGN KEY (project_id, from_client_id)
+    REFERENCES canvas_nodes(project_id, client_id) ON DELETE CASCADE;
+
+ALTER TABLE node_connections
+    ADD CONSTRAINT node_connections_to_node_fkey
+    FOREIGN KEY (project_id, to_client_id)
+    REFERENCES canvas_nodes(project_id, client_id) ON DELETE CASCADE;
+
+CREATE INDEX IF NOT EXISTS idx_node_connections_to ON node_connections(project_id, to_client_id);
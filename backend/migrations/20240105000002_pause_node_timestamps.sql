@@ -0,0 +1,5 @@
+-- The payload backfill in 20240106000001 is not an edit, so updated_at must
+-- survive it. 20240106000002 turns the trigger back on; where the backfill
+-- already ran, the pair is applied back to back and changes nothing.
+
+ALTER TABLE canvas_nodes DISABLE TRIGGER set_canvas_nodes_timestamp;
@@ -0,0 +1,30 @@
+-- Structured editor data (API endpoints, CLI steps, DB schemas, payment plans)
+-- validated against the node type's schema before it is written
+
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS payload JSONB;
+
+-- Backfill from the JSON the editors have been writing into generated_code
+CREATE OR REPLACE FUNCTION pg_temp.try_jsonb(raw TEXT) RETURNS JSONB AS $$
+BEGIN
+    RETURN raw::jsonb;
+EXCEPTION WHEN others THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql IMMUTABLE;
+
+UPDATE canvas_nodes n
+SET payload = parsed.value
+FROM (
+    SELECT id, pg_temp.try_jsonb(generated_code) AS value
+    FROM canvas_nodes
+    WHERE node_type IN ('api', 'cli', 'database', 'payment')
+      AND payload IS NULL
+      AND generated_code IS NOT NULL
+) parsed
+WHERE n.id = parsed.id
+  AND CASE n.node_type
+        WHEN 'api'      THEN jsonb_typeof(parsed.value) = 'array'
+        WHEN 'cli'      THEN jsonb_typeof(parsed.value) = 'array'
+        WHEN 'database' THEN jsonb_typeof(parsed.value -> 'tables') = 'array'
+        WHEN 'payment'  THEN parsed.value ? 'provider'
+      END;
@@ -0,0 +1,3 @@
+-- Re-enable the trigger paused by 20240105000002 for the payload backfill
+
+ALTER TABLE canvas_nodes ENABLE TRIGGER set_canvas_nodes_timestamp;
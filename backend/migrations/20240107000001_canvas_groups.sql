@@ -0,0 +1,29 @@
+-- Groups (frames): titled rectangles that contain nodes and can nest
+
+CREATE TABLE IF NOT EXISTS canvas_groups (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id  UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    parent_id   UUID REFERENCES canvas_groups(id) ON DELETE CASCADE,
+    title       TEXT NOT NULL DEFAULT '',
+    color       TEXT NOT NULL DEFAULT '#64748b',
+    x           DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+    y           DOUBLE PRECISION NOT NULL DEFAULT 0.0,
+    width       DOUBLE PRECISION NOT NULL DEFAULT 400.0,
+    height      DOUBLE PRECISION NOT NULL DEFAULT 300.0,
+    collapsed   BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_canvas_groups_project_id ON canvas_groups(project_id);
+CREATE INDEX IF NOT EXISTS idx_canvas_groups_parent_id ON canvas_groups(parent_id);
+
+CREATE TRIGGER set_canvas_groups_timestamp
+BEFORE UPDATE ON canvas_groups
+FOR EACH ROW EXECUTE FUNCTION trigger_set_timestamp();
+
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS group_id UUID
+    REFERENCES canvas_groups(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_canvas_nodes_group_id ON canvas_nodes(group_id)
+    WHERE group_id IS NOT NULL;
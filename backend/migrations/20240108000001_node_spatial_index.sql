@@ -0,0 +1,8 @@
+-- Spatial index for viewport (bbox) queries over large canvases.
+-- The indexed expression must match nodes::NODE_BOX exactly.
+
+CREATE EXTENSION IF NOT EXISTS btree_gist;
+
+CREATE INDEX IF NOT EXISTS idx_canvas_nodes_bbox ON canvas_nodes
+    USING gist (project_id, box(point(x, y), point(x + width, y + height)))
+    WHERE deleted_at IS NULL;
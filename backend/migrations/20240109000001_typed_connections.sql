@@ -0,0 +1,20 @@
+-- Connections carry a kind, optional label, port names and style metadata
+
+DO $$ BEGIN
+    CREATE TYPE connection_kind AS ENUM (
+        'data_flow', 'navigation', 'depends_on', 'env_binding'
+    );
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE node_connections
+    ADD COLUMN IF NOT EXISTS kind        connection_kind NOT NULL DEFAULT 'data_flow',
+    ADD COLUMN IF NOT EXISTS label       TEXT,
+    ADD COLUMN IF NOT EXISTS source_port TEXT,
+    ADD COLUMN IF NOT EXISTS target_port TEXT,
+    ADD COLUMN IF NOT EXISTS style       JSONB NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS updated_at  TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+CREATE TRIGGER set_node_connections_timestamp
+BEFORE UPDATE ON node_connections
+FOR EACH ROW EXECUTE FUNCTION trigger_set_timestamp();
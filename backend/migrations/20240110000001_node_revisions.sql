@@ -0,0 +1,15 @@
+-- History of a node's text fields (title, description, content, generated code)
+
+CREATE TABLE IF NOT EXISTS node_revisions (
+    id              UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    node_id         UUID NOT NULL REFERENCES canvas_nodes(id) ON DELETE CASCADE,
+    revision        INTEGER NOT NULL,
+    title           TEXT NOT NULL,
+    description     TEXT NOT NULL,
+    content         TEXT,
+    generated_code  TEXT,
+    -- NULL for the baseline captured before a node's first tracked edit
+    author_id       UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(node_id, revision)
+);
@@ -0,0 +1,40 @@
+-- Review threads anchored to a node, or to an element inside a design node
+
+CREATE TABLE IF NOT EXISTS comment_threads (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id   UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    node_id      UUID NOT NULL REFERENCES canvas_nodes(id) ON DELETE CASCADE,
+    -- CSS selector of the element, in the same form as ElementLink.selector
+    selector     TEXT,
+    created_by   UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at  TIMESTAMPTZ,
+    resolved_by  UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_threads_project_id ON comment_threads(project_id);
+CREATE INDEX IF NOT EXISTS idx_comment_threads_node_id ON comment_threads(node_id);
+
+CREATE TRIGGER set_comment_threads_timestamp
+BEFORE UPDATE ON comment_threads
+FOR EACH ROW EXECUTE FUNCTION trigger_set_timestamp();
+
+CREATE TABLE IF NOT EXISTS comments (
+    id          UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    thread_id   UUID NOT NULL REFERENCES comment_threads(id) ON DELETE CASCADE,
+    author_id   UUID REFERENCES users(id) ON DELETE SET NULL,
+    body        TEXT NOT NULL,
+    edited_at   TIMESTAMPTZ,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_comments_thread_id ON comments(thread_id, created_at);
+
+CREATE TABLE IF NOT EXISTS comment_mentions (
+    comment_id  UUID NOT NULL REFERENCES comments(id) ON DELETE CASCADE,
+    user_id     UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_comment_mentions_user_id ON comment_mentions(user_id);
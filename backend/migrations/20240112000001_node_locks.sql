@@ -0,0 +1,11 @@
+-- Soft edit locks: one holder per node until the lock expires or is released
+
+CREATE TABLE IF NOT EXISTS node_locks (
+    node_id      UUID PRIMARY KEY REFERENCES canvas_nodes(id) ON DELETE CASCADE,
+    project_id   UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    holder_id    UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    acquired_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at   TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_node_locks_project_id ON node_locks(project_id, expires_at);
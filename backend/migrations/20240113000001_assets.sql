@@ -0,0 +1,23 @@
+-- Uploaded files, stored by content hash and deduplicated per project
+
+CREATE TABLE IF NOT EXISTS assets (
+    id             UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id     UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    -- Hex SHA-256 of the bytes; also the storage key, so identical files
+    -- in different projects share one stored object
+    sha256         TEXT NOT NULL,
+    file_name      TEXT NOT NULL,
+    content_type   TEXT NOT NULL,
+    size_bytes     BIGINT NOT NULL,
+    width          INTEGER,
+    height         INTEGER,
+    has_thumbnail  BOOLEAN NOT NULL DEFAULT FALSE,
+    uploaded_by    UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at     TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(project_id, sha256)
+);
+
+CREATE INDEX IF NOT EXISTS idx_assets_sha256 ON assets(sha256);
+
+-- Ids of assets a node uses, as a JSON array of UUID strings
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS asset_ids JSONB NOT NULL DEFAULT '[]'::jsonb;
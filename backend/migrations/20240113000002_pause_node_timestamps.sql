@@ -0,0 +1,4 @@
+-- The status_changed_at backfill in 20240114000001 is not an edit, so
+-- updated_at must survive it. 20240114000002 turns the trigger back on.
+
+ALTER TABLE canvas_nodes DISABLE TRIGGER set_canvas_nodes_timestamp;
@@ -0,0 +1,57 @@
+-- Timestamped status changes, so stuck generations can be detected and a
+-- node's lifecycle can be reviewed
+
+ALTER TABLE canvas_nodes ADD COLUMN IF NOT EXISTS status_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+UPDATE canvas_nodes SET status_changed_at = updated_at;
+
+CREATE TABLE IF NOT EXISTS node_status_changes (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    node_id      UUID NOT NULL REFERENCES canvas_nodes(id) ON DELETE CASCADE,
+    -- NULL for the status a node was created with
+    from_status  node_status,
+    to_status    node_status NOT NULL,
+    changed_at   TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_node_status_changes_node_id
+    ON node_status_changes(node_id, changed_at);
+
+-- The sweeper only ever looks at nodes that are mid-generation or running
+CREATE INDEX IF NOT EXISTS idx_canvas_nodes_busy
+    ON canvas_nodes(status_changed_at)
+    WHERE status IN ('generating', 'running') AND deleted_at IS NULL;
+
+-- Every write path (single edits, bulk saves, the sweeper) goes through these
+CREATE OR REPLACE FUNCTION trigger_stamp_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' OR NEW.status IS DISTINCT FROM OLD.status THEN
+        NEW.status_changed_at = NOW();
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION trigger_log_status_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO node_status_changes (node_id, to_status, changed_at)
+        VALUES (NEW.id, NEW.status, NEW.status_changed_at);
+    ELSIF NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO node_status_changes (node_id, from_status, to_status, changed_at)
+        VALUES (NEW.id, OLD.status, NEW.status, NEW.status_changed_at);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS stamp_canvas_nodes_status ON canvas_nodes;
+CREATE TRIGGER stamp_canvas_nodes_status
+BEFORE INSERT OR UPDATE OF status ON canvas_nodes
+FOR EACH ROW EXECUTE FUNCTION trigger_stamp_status_change();
+
+DROP TRIGGER IF EXISTS log_canvas_nodes_status ON canvas_nodes;
+CREATE TRIGGER log_canvas_nodes_status
+AFTER INSERT OR UPDATE OF status ON canvas_nodes
+FOR EACH ROW EXECUTE FUNCTION trigger_log_status_change();
@@ -0,0 +1,11 @@
+-- Where 20240114000001 already ran with the trigger on, it moved updated_at
+-- of every node to the time it was applied and copied the old value into
+-- status_changed_at. Put it back on nodes untouched since, then re-enable the
+-- trigger paused by 20240113000002.
+
+UPDATE canvas_nodes SET updated_at = status_changed_at
+WHERE updated_at = (
+    SELECT installed_on FROM _sqlx_migrations WHERE version = 20240114000001
+);
+
+ALTER TABLE canvas_nodes ENABLE TRIGGER set_canvas_nodes_timestamp;
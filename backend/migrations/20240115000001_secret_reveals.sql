@@ -0,0 +1,16 @@
+-- Audit trail of every plaintext read of an encrypted node secret. Rows
+-- outlive the node and the user so the trail stays complete.
+
+CREATE TABLE IF NOT EXISTS secret_reveals (
+    id           UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id   UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    node_id      UUID REFERENCES canvas_nodes(id) ON DELETE SET NULL,
+    client_id    TEXT NOT NULL,
+    -- e.g. env.STRIPE_SECRET_KEY or payment.webhookSecret
+    secret_name  TEXT NOT NULL,
+    user_id      UUID REFERENCES users(id) ON DELETE SET NULL,
+    revealed_at  TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_secret_reveals_project_id
+    ON secret_reveals(project_id, revealed_at DESC);
@@ -0,0 +1,9 @@
+-- Which generated variation the user picked for each source node
+
+ALTER TABLE ui_variations
+    ADD COLUMN IF NOT EXISTS selected_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS selected_by UUID REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ui_variations_selected
+    ON ui_variations(project_id, source_node_client_id)
+    WHERE selected_at IS NOT NULL;
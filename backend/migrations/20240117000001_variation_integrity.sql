@@ -0,0 +1,31 @@
+-- Variations belong to their source node and are stored once per distinct body
+-- Orphaned rows and duplicates left by earlier versions are dropped so the
+-- constraints can be added; of duplicates, a selected row or else the oldest is kept
+
+DELETE FROM ui_variations v
+WHERE NOT EXISTS (
+    SELECT 1 FROM canvas_nodes n
+    WHERE n.project_id = v.project_id AND n.client_id = v.source_node_client_id
+);
+
+ALTER TABLE ui_variations ADD COLUMN IF NOT EXISTS content_hash TEXT NOT NULL
+    GENERATED ALWAYS AS (md5(preview_html || E'\x1f' || code)) STORED;
+
+DELETE FROM ui_variations v
+USING (
+    SELECT id, ROW_NUMBER() OVER (
+        PARTITION BY project_id, source_node_client_id, content_hash
+        ORDER BY selected_at IS NULL, created_at, id
+    ) AS rank
+    FROM ui_variations
+) d
+WHERE v.id = d.id AND d.rank > 1;
+
+ALTER TABLE ui_variations
+    ADD CONSTRAINT ui_variations_source_node_fkey
+    FOREIGN KEY (project_id, source_node_client_id)
+    REFERENCES canvas_nodes(project_id, client_id) ON DELETE CASCADE;
+
+DROP INDEX IF EXISTS idx_ui_variations_source_node;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ui_variations_content
+    ON ui_variations(project_id, source_node_client_id, content_hash);
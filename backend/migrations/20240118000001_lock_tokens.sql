@@ -0,0 +1,6 @@
+-- Locks belong to an editing session, identified by a token it sends back on
+-- writes, rather than to the user: the same owner in two tabs must not
+-- overwrite each other. Locks taken before this get a token nobody holds and
+-- lapse with their TTL.
+
+ALTER TABLE node_locks ADD COLUMN IF NOT EXISTS token UUID NOT NULL DEFAULT uuid_generate_v4();
@@ -0,0 +1,5 @@
+-- A group deleted together with its contents goes to the trash with them, so
+-- the trashed nodes keep their membership and restoring one brings the group
+-- back. Trashed groups are purged with the nodes they held.
+
+ALTER TABLE canvas_groups ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
@@ -1,4 +1,5 @@
 use anyhow::Context;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -12,6 +13,32 @@ pub struct Config {
     pub openrouter_fallback_key: Option<String>,
     pub openrouter_base_url: String,
     pub api_key_encryption_secret: String,
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_secs: u64,
+    pub canvas_body_limit_bytes: usize,
+    pub node_lock_default_ttl_secs: i64,
+    pub node_lock_max_ttl_secs: i64,
+    pub node_generating_timeout_secs: i64,
+    pub node_running_timeout_secs: i64,
+    pub status_sweep_interval_secs: u64,
+    pub asset_max_bytes: usize,
+    pub asset_storage: AssetStorageConfig,
+}
+
+/// Where uploaded assets are kept
+#[derive(Clone, Debug)]
+pub enum AssetStorageConfig {
+    Local {
+        root: PathBuf,
+    },
+    /// Any S3-compatible service, addressed path-style
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
 }
 
 impl Config {
@@ -37,6 +64,57 @@ impl Config {
                 .unwrap_or_else(|_| "https://openrouter.ai/api/v1".into()),
             api_key_encryption_secret: std::env::var("API_KEY_ENCRYPTION_SECRET")
                 .context("API_KEY_ENCRYPTION_SECRET must be set")?,
+            trash_retention_days: std::env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "30".into())
+                .parse()?,
+            trash_purge_interval_secs: std::env::var("TRASH_PURGE_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".into())
+                .parse()?,
+            canvas_body_limit_bytes: std::env::var("CANVAS_BODY_LIMIT_BYTES")
+                .unwrap_or_else(|_| "52428800".into())
+                .parse()?,
+            node_lock_default_ttl_secs: std::env::var("NODE_LOCK_DEFAULT_TTL_SECS")
+                .unwrap_or_else(|_| "60".into())
+                .parse()?,
+            node_lock_max_ttl_secs: std::env::var("NODE_LOCK_MAX_TTL_SECS")
+                .unwrap_or_else(|_| "600".into())
+                .parse()?,
+            node_generating_timeout_secs: std::env::var("NODE_GENERATING_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "900".into())
+                .parse()?,
+            node_running_timeout_secs: std::env::var("NODE_RUNNING_TIMEOUT_SECS")
+                .unwrap_or_else(|_| "3600".into())
+                .parse()?,
+            status_sweep_interval_secs: std::env::var("STATUS_SWEEP_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".into())
+                .parse()?,
+            asset_max_bytes: std::env::var("ASSET_MAX_BYTES")
+                .unwrap_or_else(|_| "10485760".into())
+                .parse()?,
+            asset_storage: AssetStorageConfig::from_env()?,
         })
     }
 }
+
+impl AssetStorageConfig {
+    fn from_env() -> anyhow::Result<Self> {
+        let backend = std::env::var("ASSET_STORAGE").unwrap_or_else(|_| "local".into());
+        match backend.as_str() {
+            "local" => Ok(Self::Local {
+                root: std::env::var("ASSET_LOCAL_DIR")
+                    .unwrap_or_else(|_| "./data/assets".into())
+                    .into(),
+            }),
+            "s3" => Ok(Self::S3 {
+                endpoint: std::env::var("S3_ENDPOINT").context("S3_ENDPOINT must be set")?,
+                bucket: std::env::var("S3_BUCKET").context("S3_BUCKET must be set")?,
+                region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".into()),
+                access_key: std::env::var("S3_ACCESS_KEY")
+                    .context("S3_ACCESS_KEY must be set")?,
+                secret_key: std::env::var("S3_SECRET_KEY")
+                    .context("S3_SECRET_KEY must be set")?,
+            }),
+            other => anyhow::bail!("ASSET_STORAGE must be 'local' or 's3', got '{other}'"),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Reads and writes `.env` files: comments, `export` prefixes, single, double
+//! and backtick quoting, escapes in double quotes and multiline quoted values.
+//! Values are taken literally; unlike `dotenvy`, `$NAME` is never expanded,
+//! as that would read the server's own environment into user data.
+
+/// A line that cannot be read, numbered from 1
+#[derive(Debug, PartialEq, Eq)]
+pub struct DotenvError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for DotenvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Variables in file order; a name assigned twice keeps its last value
+pub fn parse(text: &str) -> Result<Vec<(String, String)>, DotenvError> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let start = i + 1;
+        let error = |message: String| DotenvError {
+            line: start,
+            message,
+        };
+        // Only the start is trimmed, a quoted value may end in spaces
+        let line = lines[i].trim_start_matches('\u{feff}').trim_start();
+        i += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix("export")
+            .filter(|rest| rest.starts_with([' ', '\t']))
+            .map_or(line, str::trim_start);
+        let (name, rest) = line
+            .split_once('=')
+            .ok_or_else(|| error(format!("expected NAME=value, got '{line}'")))?;
+        let name = name.trim_end();
+        if !is_valid_name(name) {
+            return Err(error(format!("'{name}' is not a valid variable name")));
+        }
+        let rest = rest.trim_start();
+
+        let value = match rest.chars().next() {
+            Some(quote @ ('\'' | '"' | '`')) => {
+                // Quoted values run until the closing quote, across lines if need be
+                let mut body = rest[1..].to_string();
+                let close = loop {
+                    if let Some(at) = closing_quote(&body, quote) {
+                        break at;
+                    }
+                    let next = lines
+                        .get(i)
+                        .ok_or_else(|| error(format!("unterminated {quote} quote")))?;
+                    body.push('\n');
+                    body.push_str(next);
+                    i += 1;
+                };
+                let trailing = body[close + 1..].trim();
+                if !trailing.is_empty() && !trailing.starts_with('#') {
+                    return Err(error(format!(
+                        "unexpected '{trailing}' after the closing quote"
+                    )));
+                }
+                let body = &body[..close];
+                if quote == '"' {
+                    unescape(body)
+                } else {
+                    body.to_string()
+                }
+            }
+            _ => strip_inline_comment(rest).to_string(),
+        };
+
+        match vars.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = value,
+            None => vars.push((name.to_string(), value)),
+        }
+    }
+    Ok(vars)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Byte offset of the unescaped closing `quote` in `body`
+fn closing_quote(body: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (at, c) in body.char_indices() {
+        match c {
+            '\\' if quote == '"' && !escaped => escaped = true,
+            c if c == quote && !escaped => return Some(at),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+fn unescape(body: &str) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(c @ ('"' | '\\' | '$')) => out.push(c),
+            // Unknown escapes are kept as written
+            Some(c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// An unquoted value ends at a `#` that follows whitespace
+fn strip_inline_comment(value: &str) -> &str {
+    let end = value
+        .char_indices()
+        .find(|&(at, c)| c == '#' && value[..at].ends_with([' ', '\t']))
+        .map_or(value.len(), |(at, _)| at);
+    value[..end].trim_end()
+}
+
+/// Render variables in the given order, quoting only where needed. An
+/// example file keeps the names and blanks every value.
+pub fn render<'a>(
+    header: &str,
+    vars: impl IntoIterator<Item = (&'a str, &'a str)>,
+    example: bool,
+) -> String {
+    let mut out = String::new();
+    for line in header.lines() {
+        out.push_str("# ");
+        out.push_str(line);
+        out.push('\n');
+    }
+    for (name, value) in vars {
+        out.push_str(name);
+        out.push('=');
+        if !example {
+            out.push_str(&quote(value));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn quote(value: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:@+,%=".contains(c);
+    if value.chars().all(plain) {
+        return value.to_string();
+    }
+    if !value.contains(['\'', '\n', '\r']) {
+        return format!("'{value}'");
+    }
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '$' => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_comments_quotes_escapes_and_multiline_values() {
+        let text = r#"
+# database
+export DATABASE_URL=postgres://localhost/app   # local only
+PORT = 3000
+EMPTY=
+GREETING="Hello\n\"world\" \$HOME"
+LITERAL='no \n escapes # here'
+PRIVATE_KEY="-----BEGIN KEY-----
+abc
+-----END KEY-----" # pem
+HASH=abc#def
+PORT=4000
+"#;
+        let vars = parse(text).unwrap();
+        let get = |name: &str| {
+            vars.iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("DATABASE_URL"), Some("postgres://localhost/app"));
+        assert_eq!(get("PORT"), Some("4000"));
+        assert_eq!(get("EMPTY"), Some(""));
+        assert_eq!(get("GREETING"), Some("Hello\n\"world\" $HOME"));
+        assert_eq!(get("LITERAL"), Some(r"no \n escapes # here"));
+        assert_eq!(
+            get("PRIVATE_KEY"),
+            Some("-----BEGIN KEY-----\nabc\n-----END KEY-----")
+        );
+        assert_eq!(get("HASH"), Some("abc#def"));
+        assert_eq!(vars.len(), 7, "PORT is listed once");
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        assert_eq!(parse("A=1\nnot a var").unwrap_err().line, 2);
+        assert_eq!(parse("1A=x").unwrap_err().line, 1);
+        assert_eq!(parse("A=1\nB=\"open\nstill open").unwrap_err().line, 2);
+        assert_eq!(parse("A='x' y").unwrap_err().line, 1);
+    }
+
+    #[test]
+    fn rendered_files_parse_back() {
+        let vars = [
+            ("PLAIN", "postgres://u@h:5432/db"),
+            ("SPACES", "two words"),
+            ("QUOTES", "it's \"quoted\" $HOME \\ ok"),
+            ("LINES", "a\nb"),
+            ("EMPTY", ""),
+        ];
+        let text = render("Exported", vars, false);
+        assert!(text.starts_with("# Exported\nPLAIN=postgres://u@h:5432/db\n"));
+        let parsed = parse(&text).unwrap();
+        let expected: Vec<(String, String)> = vars
+            .iter()
+            .map(|(n, v)| (n.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(parsed, expected);
+
+        assert_eq!(render("", vars, true).lines().next(), Some("PLAIN="));
+    }
+}
@@ -0,0 +1,91 @@
+//! Resolves element link selectors against a design node's HTML
+
+use scraper::{ElementRef, Html, Selector};
+
+/// Why a selector cannot be resolved in a design
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorProblem {
+    /// The node has no HTML to look in
+    NoContent,
+    /// Not valid CSS selector syntax
+    InvalidSelector,
+    /// Valid, but no element in the HTML matches
+    NoMatch,
+}
+
+/// A node's HTML, parsed once and queried per link
+pub struct Design {
+    html: Option<Html>,
+}
+
+impl Design {
+    pub fn parse(content: Option<&str>) -> Self {
+        let html = content
+            .filter(|c| !c.trim().is_empty())
+            .map(Html::parse_document);
+        Self { html }
+    }
+
+    /// Element type of the first element `selector` matches
+    pub fn resolve(&self, selector: &str) -> Result<&'static str, SelectorProblem> {
+        let html = self.html.as_ref().ok_or(SelectorProblem::NoContent)?;
+        let selector = Selector::parse(selector).map_err(|_| SelectorProblem::InvalidSelector)?;
+        html.select(&selector)
+            .next()
+            .map(element_type)
+            .ok_or(SelectorProblem::NoMatch)
+    }
+}
+
+/// Map an element to the coarse types the editor distinguishes
+fn element_type(el: ElementRef) -> &'static str {
+    let attr = |name| el.value().attr(name).map(str::to_ascii_lowercase);
+    match attr("role").as_deref() {
+        Some("button") => return "button",
+        Some("link") => return "link",
+        _ => {}
+    }
+    match el.value().name() {
+        "a" => "link",
+        "button" => "button",
+        "form" => "form",
+        "input" => match attr("type").as_deref() {
+            Some("submit" | "button" | "reset" | "image") => "button",
+            _ => "input",
+        },
+        "textarea" | "select" => "input",
+        "img" | "picture" | "svg" => "image",
+        "video" | "audio" => "media",
+        "nav" | "ul" | "ol" | "menu" => "list",
+        _ => "element",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_selectors_and_infers_types() {
+        let design = Design::parse(Some(
+            r#"<nav><a href="/">Home</a></nav>
+               <form id="signup">
+                 <input name="email"><input type="submit" value="Go">
+                 <div role="button" class="cta">More</div>
+               </form>
+               <img src="logo.png">"#,
+        ));
+        assert_eq!(design.resolve("nav a"), Ok("link"));
+        assert_eq!(design.resolve("#signup"), Ok("form"));
+        assert_eq!(design.resolve("input[name=email]"), Ok("input"));
+        assert_eq!(design.resolve("input[type=submit]"), Ok("button"));
+        assert_eq!(design.resolve(".cta"), Ok("button"));
+        assert_eq!(design.resolve("body > img"), Ok("image"));
+        assert_eq!(design.resolve("#missing"), Err(SelectorProblem::NoMatch));
+        assert_eq!(design.resolve("a[["), Err(SelectorProblem::InvalidSelector));
+        assert_eq!(
+            Design::parse(Some("  ")).resolve("a"),
+            Err(SelectorProblem::NoContent)
+        );
+    }
+}
@@ -0,0 +1,336 @@
+//! Resolves the variables a node sees from the env nodes bound to it.
+//!
+//! Each bound env node is first layered over its chain of bases, so a node
+//! overrides what it inherits. Bound nodes for the requested environment then
+//! beat ones that apply to every environment; bound nodes of equal standing
+//! that disagree are conflicts, and the most recently updated one wins.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::models::environment::{
+    BrokenBaseReason, BrokenEnvBase, EffectiveEnvironment, EffectiveVariable, EnvConflict,
+    EnvSource, MissingVariable,
+};
+use crate::models::node_payload::DeployEnvironment;
+use crate::secrets::MASK;
+
+/// A live env node with its values decrypted
+pub struct EnvNode {
+    pub client_id: String,
+    pub title: String,
+    pub environment: Option<DeployEnvironment>,
+    pub base: Option<String>,
+    pub required: Vec<String>,
+    pub vars: HashMap<String, EnvValue>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct EnvValue {
+    pub value: String,
+    pub secret: bool,
+}
+
+/// A value with the env node it came from
+#[derive(Clone)]
+struct Layered {
+    value: EnvValue,
+    source: String,
+    overrides: Vec<String>,
+}
+
+/// Everything a project knows about its env nodes
+pub struct EnvCatalog {
+    pub env_nodes: HashMap<String, EnvNode>,
+    /// Live nodes that are not env nodes, to tell a wrong base from a missing one
+    pub other_nodes: HashSet<String>,
+}
+
+impl EnvCatalog {
+    /// `node` followed by its bases, nearest first, stopping at the first base
+    /// that cannot be followed
+    fn chain<'a>(&'a self, node: &'a EnvNode, broken: &mut Vec<BrokenEnvBase>) -> Vec<&'a EnvNode> {
+        let mut chain = vec![node];
+        let mut current = node;
+        while let Some(base) = current.base.as_deref() {
+            let reason = if chain.iter().any(|n| n.client_id == base) {
+                BrokenBaseReason::Cycle
+            } else if let Some(next) = self.env_nodes.get(base) {
+                chain.push(next);
+                current = next;
+                continue;
+            } else if self.other_nodes.contains(base) {
+                BrokenBaseReason::NotEnv
+            } else {
+                BrokenBaseReason::Missing
+            };
+            if !broken.iter().any(|b| b.client_id == current.client_id) {
+                broken.push(BrokenEnvBase {
+                    client_id: current.client_id.clone(),
+                    base: base.to_string(),
+                    reason,
+                });
+            }
+            break;
+        }
+        chain
+    }
+
+    /// Variables `client_id` sees in `environment` through the env nodes in `bound`
+    pub fn resolve(
+        &self,
+        client_id: &str,
+        environment: DeployEnvironment,
+        bound: &[String],
+    ) -> EffectiveEnvironment {
+        let mut applicable: Vec<&EnvNode> = bound
+            .iter()
+            .filter_map(|id| self.env_nodes.get(id))
+            .filter(|n| n.environment.is_none_or(|e| e == environment))
+            .collect();
+        applicable.sort_by(|a, b| {
+            b.environment
+                .is_some()
+                .cmp(&a.environment.is_some())
+                .then(b.updated_at.cmp(&a.updated_at))
+                .then(a.client_id.cmp(&b.client_id))
+        });
+        applicable.dedup_by(|a, b| a.client_id == b.client_id);
+
+        let mut broken_bases = Vec::new();
+        let mut sources = Vec::new();
+        let mut required: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut merged: BTreeMap<String, (Layered, bool)> = BTreeMap::new();
+        let mut conflicts: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+        for node in applicable {
+            let chain = self.chain(node, &mut broken_bases);
+            sources.push(EnvSource {
+                client_id: node.client_id.clone(),
+                title: node.title.clone(),
+                environment: node.environment,
+                inherits: chain[1..].iter().map(|n| n.client_id.clone()).collect(),
+            });
+
+            // Farthest base first, so nearer nodes override
+            let mut layered: HashMap<&str, Layered> = HashMap::new();
+            for env in chain.iter().rev() {
+                for name in &env.required {
+                    let by = required.entry(name.clone()).or_default();
+                    if !by.contains(&env.client_id) {
+                        by.push(env.client_id.clone());
+                    }
+                }
+                for (name, value) in &env.vars {
+                    let mut overrides = Vec::new();
+                    if let Some(prev) = layered.remove(name.as_str()) {
+                        overrides = prev.overrides;
+                        overrides.push(prev.source);
+                    }
+                    let entry = Layered {
+                        value: value.clone(),
+                        source: env.client_id.clone(),
+                        overrides,
+                    };
+                    layered.insert(name, entry);
+                }
+            }
+
+            let specific = node.environment.is_some();
+            for (name, candidate) in layered {
+                let Some((winner, winner_specific)) = merged.get_mut(name) else {
+                    merged.insert(name.to_string(), (candidate, specific));
+                    continue;
+                };
+                if winner.source == candidate.source {
+                    continue;
+                }
+                if *winner_specific == specific && winner.value.value != candidate.value.value {
+                    let sources = conflicts
+                        .entry(name.to_string())
+                        .or_insert_with(|| vec![winner.source.clone()]);
+                    if !sources.contains(&candidate.source) {
+                        sources.push(candidate.source);
+                    }
+                } else if !winner.overrides.contains(&candidate.source) {
+                    winner.overrides.push(candidate.source);
+                }
+            }
+        }
+
+        let missing = required
+            .into_iter()
+            .filter(|(name, _)| {
+                merged
+                    .get(name)
+                    .is_none_or(|(v, _)| v.value.value.trim().is_empty())
+            })
+            .map(|(name, required_by)| MissingVariable { name, required_by })
+            .collect();
+        let variables = merged
+            .into_iter()
+            .map(|(name, (layered, _))| EffectiveVariable {
+                name,
+                value: if layered.value.secret {
+                    MASK.to_string()
+                } else {
+                    layered.value.value
+                },
+                secret: layered.value.secret,
+                source: layered.source,
+                overrides: layered.overrides,
+            })
+            .collect();
+        broken_bases.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+
+        EffectiveEnvironment {
+            client_id: client_id.to_string(),
+            environment,
+            sources,
+            variables,
+            conflicts: conflicts
+                .into_iter()
+                .map(|(name, sources)| EnvConflict { name, sources })
+                .collect(),
+            missing,
+            broken_bases,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn env(
+        id: &str,
+        environment: Option<DeployEnvironment>,
+        base: Option<&str>,
+        vars: &[(&str, &str)],
+        minute: u32,
+    ) -> EnvNode {
+        EnvNode {
+            client_id: id.to_string(),
+            title: id.to_string(),
+            environment,
+            base: base.map(str::to_string),
+            required: Vec::new(),
+            vars: vars
+                .iter()
+                .map(|(k, v)| {
+                    let value = EnvValue {
+                        value: v.to_string(),
+                        secret: k.ends_with("_SECRET"),
+                    };
+                    (k.to_string(), value)
+                })
+                .collect(),
+            updated_at: Utc.with_ymd_and_hms(2024, 1, 1, 0, minute, 0).unwrap(),
+        }
+    }
+
+    fn catalog(nodes: Vec<EnvNode>) -> EnvCatalog {
+        EnvCatalog {
+            env_nodes: nodes
+                .into_iter()
+                .map(|n| (n.client_id.clone(), n))
+                .collect(),
+            other_nodes: HashSet::from(["api".to_string()]),
+        }
+    }
+
+    fn var<'a>(env: &'a EffectiveEnvironment, name: &str) -> &'a EffectiveVariable {
+        env.variables.iter().find(|v| v.name == name).unwrap()
+    }
+
+    #[test]
+    fn environment_specific_nodes_override_their_base_and_shared_nodes() {
+        use DeployEnvironment::*;
+        let mut base = env("base", None, None, &[("PORT", "3000"), ("DB_URL", "")], 0);
+        base.required = vec!["DB_URL".into(), "API_SECRET".into()];
+        let cat = catalog(vec![
+            base,
+            env(
+                "prod",
+                Some(Production),
+                Some("base"),
+                &[("DB_URL", "postgres://prod")],
+                1,
+            ),
+            env(
+                "dev",
+                Some(Development),
+                Some("base"),
+                &[("DB_URL", "postgres://dev")],
+                2,
+            ),
+            env(
+                "shared",
+                None,
+                None,
+                &[("PORT", "8080"), ("API_SECRET", "s3")],
+                3,
+            ),
+        ]);
+        let bound = ["prod", "dev", "shared"].map(String::from);
+
+        let prod = cat.resolve("api", Production, &bound);
+        assert_eq!(
+            prod.sources
+                .iter()
+                .map(|s| s.client_id.as_str())
+                .collect::<Vec<_>>(),
+            ["prod", "shared"]
+        );
+        assert_eq!(var(&prod, "DB_URL").value, "postgres://prod");
+        assert_eq!(var(&prod, "DB_URL").overrides, ["base"]);
+        // prod inherits PORT from base and outranks the shared node
+        assert_eq!(var(&prod, "PORT").value, "3000");
+        assert_eq!(var(&prod, "PORT").overrides, ["shared"]);
+        assert_eq!(var(&prod, "API_SECRET").value, MASK);
+        assert!(prod.conflicts.is_empty() && prod.missing.is_empty());
+
+        let staging = cat.resolve("api", Staging, &bound);
+        assert_eq!(staging.variables.len(), 2);
+        assert!(
+            staging.missing.is_empty(),
+            "DB_URL is only required through base"
+        );
+    }
+
+    #[test]
+    fn reports_conflicts_missing_values_and_broken_bases() {
+        use DeployEnvironment::*;
+        let mut a = env("a", Some(Production), Some("api"), &[("KEY", "1")], 5);
+        a.required = vec!["TOKEN".into()];
+        let cat = catalog(vec![
+            a,
+            env("b", Some(Production), Some("c"), &[("KEY", "2")], 4),
+            env("c", None, Some("b"), &[], 0),
+            env("d", Some(Production), Some("gone"), &[("KEY", "1")], 3),
+        ]);
+        let bound = ["a", "b", "d"].map(String::from);
+        let env = cat.resolve("api", Production, &bound);
+
+        assert_eq!(var(&env, "KEY").value, "1", "the newest node wins");
+        assert_eq!(env.conflicts.len(), 1);
+        assert_eq!(env.conflicts[0].sources, ["a", "b"]);
+        assert_eq!(env.missing[0].name, "TOKEN");
+        assert_eq!(env.missing[0].required_by, ["a"]);
+        let reasons: Vec<_> = env
+            .broken_bases
+            .iter()
+            .map(|b| (b.client_id.as_str(), b.reason))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("a", BrokenBaseReason::NotEnv),
+                ("c", BrokenBaseReason::Cycle),
+                ("d", BrokenBaseReason::Missing),
+            ]
+        );
+    }
+}
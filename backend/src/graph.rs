@@ -0,0 +1,322 @@
+//! Directed graph algorithms over a project's node connections
+
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, VecDeque};
+
+/// Adjacency-list view of a canvas; nodes keep their input order
+pub struct Graph {
+    ids: Vec<String>,
+    index: HashMap<String, usize>,
+    succ: Vec<Vec<usize>>,
+    pred: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    /// Build from node ids and `(from, to)` pairs; edges naming unknown
+    /// nodes are ignored
+    pub fn new(ids: Vec<String>, edges: &[(String, String)]) -> Self {
+        let index: HashMap<String, usize> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.clone(), i))
+            .collect();
+        let mut succ = vec![Vec::new(); ids.len()];
+        let mut pred = vec![Vec::new(); ids.len()];
+        for (from, to) in edges {
+            if let (Some(&f), Some(&t)) = (index.get(from), index.get(to)) {
+                succ[f].push(t);
+                pred[t].push(f);
+            }
+        }
+        Self {
+            ids,
+            index,
+            succ,
+            pred,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.index.contains_key(id)
+    }
+
+    /// Strongly connected components that form cycles, including self-loops.
+    /// Each cycle lists its nodes in input order.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut cycles: Vec<Vec<usize>> = self
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.succ[c[0]].contains(&c[0]))
+            .collect();
+        for c in cycles.iter_mut() {
+            c.sort_unstable();
+        }
+        cycles.sort_unstable_by_key(|c| c[0]);
+        cycles.into_iter().map(|c| self.names(&c)).collect()
+    }
+
+    /// Kahn's algorithm, ties broken by input order. `None` if there is a cycle.
+    pub fn topological_order(&self) -> Option<Vec<String>> {
+        let mut indegree: Vec<usize> = self.pred.iter().map(Vec::len).collect();
+        let mut ready: BTreeSet<usize> =
+            (0..self.ids.len()).filter(|&i| indegree[i] == 0).collect();
+        let mut order = Vec::with_capacity(self.ids.len());
+        while let Some(n) = ready.pop_first() {
+            order.push(n);
+            for &t in &self.succ[n] {
+                indegree[t] -= 1;
+                if indegree[t] == 0 {
+                    ready.insert(t);
+                }
+            }
+        }
+        (order.len() == self.ids.len()).then(|| self.names(&order))
+    }
+
+    /// Nodes with no connections at all
+    pub fn orphans(&self) -> Vec<String> {
+        let lonely: Vec<usize> = (0..self.ids.len())
+            .filter(|&i| self.succ[i].is_empty() && self.pred[i].is_empty())
+            .collect();
+        self.names(&lonely)
+    }
+
+    /// Nodes that cannot be reached from `roots`. Without explicit roots, every
+    /// node without incoming connections is a root, so what remains are nodes
+    /// only reachable through a cycle.
+    pub fn unreachable(&self, roots: Option<&[String]>) -> Vec<String> {
+        let starts: Vec<usize> = match roots {
+            Some(roots) => roots
+                .iter()
+                .filter_map(|r| self.index.get(r).copied())
+                .collect(),
+            None => (0..self.ids.len())
+                .filter(|&i| self.pred[i].is_empty())
+                .collect(),
+        };
+        let seen = self.bfs(&starts, &self.succ, None);
+        let missing: Vec<usize> = (0..self.ids.len())
+            .filter(|i| !seen.contains_key(i))
+            .collect();
+        self.names(&missing)
+    }
+
+    /// Nodes reachable from `id` with their distance in hops
+    pub fn descendants(&self, id: &str, max_depth: Option<usize>) -> Vec<(String, usize)> {
+        self.related(id, &self.succ, max_depth)
+    }
+
+    /// Nodes that can reach `id` with their distance in hops
+    pub fn ancestors(&self, id: &str, max_depth: Option<usize>) -> Vec<(String, usize)> {
+        self.related(id, &self.pred, max_depth)
+    }
+
+    /// Fewest-hop path from `from` to `to`, optionally ignoring direction
+    pub fn shortest_path(&self, from: &str, to: &str, undirected: bool) -> Option<Vec<String>> {
+        let (&start, &goal) = (self.index.get(from)?, self.index.get(to)?);
+        let both: Vec<Vec<usize>>;
+        let adjacency = if undirected {
+            both = self
+                .succ
+                .iter()
+                .zip(&self.pred)
+                .map(|(s, p)| s.iter().chain(p).copied().collect())
+                .collect();
+            &both
+        } else {
+            &self.succ
+        };
+
+        let mut prev: HashMap<usize, usize> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        prev.insert(start, start);
+        while let Some(n) = queue.pop_front() {
+            if n == goal {
+                let mut path = vec![goal];
+                let mut at = goal;
+                while at != start {
+                    at = prev[&at];
+                    path.push(at);
+                }
+                path.reverse();
+                return Some(self.names(&path));
+            }
+            for &t in &adjacency[n] {
+                if let Entry::Vacant(e) = prev.entry(t) {
+                    e.insert(n);
+                    queue.push_back(t);
+                }
+            }
+        }
+        None
+    }
+
+    fn related(
+        &self,
+        id: &str,
+        adjacency: &[Vec<usize>],
+        max_depth: Option<usize>,
+    ) -> Vec<(String, usize)> {
+        let Some(&start) = self.index.get(id) else {
+            return Vec::new();
+        };
+        let mut found: Vec<(usize, usize)> = self
+            .bfs(&[start], adjacency, max_depth)
+            .into_iter()
+            .filter(|&(n, _)| n != start)
+            .collect();
+        found.sort_unstable_by_key(|&(n, depth)| (depth, n));
+        found
+            .into_iter()
+            .map(|(n, depth)| (self.ids[n].clone(), depth))
+            .collect()
+    }
+
+    /// Breadth-first distances from `starts`
+    fn bfs(
+        &self,
+        starts: &[usize],
+        adjacency: &[Vec<usize>],
+        max_depth: Option<usize>,
+    ) -> HashMap<usize, usize> {
+        let mut depth: HashMap<usize, usize> = starts.iter().map(|&s| (s, 0)).collect();
+        let mut queue: VecDeque<usize> = starts.iter().copied().collect();
+        while let Some(n) = queue.pop_front() {
+            let d = depth[&n];
+            if max_depth.is_some_and(|max| d >= max) {
+                continue;
+            }
+            for &t in &adjacency[n] {
+                if let Entry::Vacant(e) = depth.entry(t) {
+                    e.insert(d + 1);
+                    queue.push_back(t);
+                }
+            }
+        }
+        depth
+    }
+
+    /// Iterative Tarjan's algorithm
+    fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        const UNVISITED: usize = usize::MAX;
+        let n = self.ids.len();
+        let mut order = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut counter = 0;
+
+        for root in 0..n {
+            if order[root] != UNVISITED {
+                continue;
+            }
+            let mut work = vec![(root, 0usize)];
+            while let Some((v, next)) = work.pop() {
+                if next == 0 {
+                    order[v] = counter;
+                    low[v] = counter;
+                    counter += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                }
+                if let Some(&w) = self.succ[v].get(next) {
+                    work.push((v, next + 1));
+                    if order[w] == UNVISITED {
+                        work.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(order[w]);
+                    }
+                    continue;
+                }
+                if low[v] == order[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(w);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+                if let Some(&(parent, _)) = work.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+            }
+        }
+        components
+    }
+
+    fn names(&self, nodes: &[usize]) -> Vec<String> {
+        nodes.iter().map(|&n| self.ids[n].clone()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(ids: &[&str], edges: &[(&str, &str)]) -> Graph {
+        Graph::new(
+            ids.iter().map(|s| s.to_string()).collect(),
+            &edges
+                .iter()
+                .map(|(f, t)| (f.to_string(), t.to_string()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn detects_cycles_and_unreachable_nodes() {
+        let g = graph(
+            &["a", "b", "c", "d", "e", "f"],
+            &[
+                ("a", "b"),
+                ("b", "c"),
+                ("c", "b"),
+                ("d", "e"),
+                ("e", "d"),
+                ("e", "e"),
+            ],
+        );
+        assert_eq!(g.cycles(), vec![vec!["b", "c"], vec!["d", "e"]]);
+        assert_eq!(g.topological_order(), None);
+        assert_eq!(g.orphans(), vec!["f"]);
+        assert_eq!(g.unreachable(None), vec!["d", "e"]);
+        assert_eq!(
+            g.unreachable(Some(&["b".to_string()])),
+            vec!["a", "d", "e", "f"]
+        );
+    }
+
+    #[test]
+    fn orders_and_walks_a_dag() {
+        let g = graph(
+            &["c", "a", "b", "d"],
+            &[("a", "b"), ("b", "c"), ("a", "d"), ("d", "c")],
+        );
+        assert!(g.cycles().is_empty());
+        assert_eq!(g.topological_order().unwrap(), vec!["a", "b", "d", "c"]);
+        assert_eq!(
+            g.descendants("a", None),
+            vec![("b".into(), 1), ("d".into(), 1), ("c".into(), 2)]
+        );
+        assert_eq!(
+            g.ancestors("c", Some(1)),
+            vec![("b".into(), 1), ("d".into(), 1)]
+        );
+        assert_eq!(
+            g.shortest_path("a", "c", false).unwrap(),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(g.shortest_path("c", "a", false), None);
+        assert_eq!(
+            g.shortest_path("c", "a", true).unwrap(),
+            vec!["c", "b", "a"]
+        );
+    }
+}
@@ -1,10 +1,4 @@
-use aes_gcm::{
-    aead::{Aead, KeyInit, OsRng},
-    Aes256Gcm, Key, Nonce,
-};
 use axum::{extract::State, Json};
-use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::time::{Duration, Instant};
@@ -12,6 +6,7 @@ use std::time::{Duration, Instant};
 use crate::{
     error::{AppError, Result},
     middleware::auth::AuthUser,
+    secrets,
     state::{AppState, ModelCache},
 };
 
@@ -150,7 +145,7 @@ pub async fn save_key(
         return Err(AppError::Validation("API key cannot be empty".into()));
     }
 
-    let encrypted = encrypt_api_key(&req.key, &state.cfg.api_key_encryption_secret)?;
+    let encrypted = secrets::encrypt(&req.key, &state.cfg.api_key_encryption_secret)?;
 
     sqlx::query(
         "INSERT INTO user_api_keys (user_id, provider, encrypted_key)
@@ -188,58 +183,9 @@ async fn resolve_api_key(state: &AppState, user_id: uuid::Uuid) -> Result<Option
     .await?;
 
     if let Some(Some(encrypted)) = row {
-        let key = decrypt_api_key(&encrypted, &state.cfg.api_key_encryption_secret)?;
+        let key = secrets::decrypt(&encrypted, &state.cfg.api_key_encryption_secret)?;
         return Ok(Some(key));
     }
 
     Ok(state.cfg.openrouter_fallback_key.clone())
 }
-
-fn derive_key(secret: &str) -> [u8; 32] {
-    use sha2::{Digest, Sha256};
-    let hash = Sha256::digest(secret.as_bytes());
-    let mut key = [0u8; 32];
-    key.copy_from_slice(&hash);
-    key
-}
-
-fn encrypt_api_key(key: &str, secret: &str) -> Result<String> {
-    let cipher_key = derive_key(secret);
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&cipher_key));
-
-    let mut nonce_bytes = [0u8; 12];
-    OsRng.fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
-
-    let ciphertext = cipher
-        .encrypt(nonce, key.as_bytes())
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Encryption error: {e}")))?;
-
-    let mut combined = nonce_bytes.to_vec();
-    combined.extend_from_slice(&ciphertext);
-
-    Ok(BASE64.encode(combined))
-}
-
-fn decrypt_api_key(encrypted: &str, secret: &str) -> Result<String> {
-    let data = BASE64
-        .decode(encrypted)
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Base64 decode error: {e}")))?;
-
-    if data.len() < 12 {
-        return Err(AppError::Internal(anyhow::anyhow!("Invalid encrypted data")));
-    }
-
-    let (nonce_bytes, ciphertext) = data.split_at(12);
-    let nonce = Nonce::from_slice(nonce_bytes);
-
-    let cipher_key = derive_key(secret);
-    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&cipher_key));
-
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("Decryption error: {e}")))?;
-
-    String::from_utf8(plaintext)
-        .map_err(|e| AppError::Internal(anyhow::anyhow!("UTF-8 decode error: {e}")))
-}
@@ -0,0 +1,556 @@
+use axum::{
+    body::Bytes,
+    extract::{Multipart, Path, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use image::{io::Limits, ImageFormat, ImageOutputFormat};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+use std::io::Cursor;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::nodes::verify_project_owner,
+    middleware::auth::AuthUser,
+    models::asset::Asset,
+    state::AppState,
+};
+
+/// Longest edge of generated thumbnails, in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Images larger than this in either dimension are rejected before decoding
+const MAX_DECODE_DIMENSION: u32 = 12_000;
+
+/// Decoded image facts gathered off the async runtime
+struct Inspected {
+    content_type: &'static str,
+    dimensions: Option<(u32, u32)>,
+    thumbnail: Option<Vec<u8>>,
+}
+
+/// Upload a file to a project
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/assets",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    request_body(content = AssetUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, description = "Asset stored", body = Asset),
+        (status = 200, description = "The project already has this file; existing asset returned", body = Asset),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+        (status = 422, description = "Missing file, unsupported type or too large"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn upload_asset(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<Asset>)> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let (file_name, declared_type, bytes) =
+        read_file_field(&mut multipart, state.cfg.asset_max_bytes).await?;
+    let sha256 = hex::encode(Sha256::digest(&bytes));
+
+    if let Some(existing) = fetch_asset_by_hash(&state, project_id, &sha256).await? {
+        return Ok((StatusCode::OK, Json(existing)));
+    }
+
+    let inspect_bytes = bytes.clone();
+    let inspected = tokio::task::spawn_blocking(move || inspect(&inspect_bytes))
+        .await
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Asset inspection failed: {e}")))??;
+    if let Some(declared) = declared_type.as_deref() {
+        if declared != "application/octet-stream" && declared != inspected.content_type {
+            return Err(AppError::Validation(format!(
+                "File is {} but was uploaded as {declared}",
+                inspected.content_type
+            )));
+        }
+    }
+
+    // Objects are shared by every project holding the same bytes. The blob
+    // lock keeps a concurrent delete from removing the object between the
+    // check and the insert that makes this project another holder.
+    let mut tx = state.db.begin().await?;
+    lock_blob(&mut tx, &sha256).await?;
+    let stored: bool = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM assets WHERE sha256 = $1)")
+        .bind(&sha256)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !stored {
+        state
+            .storage
+            .put(&object_key(&sha256), bytes.clone(), inspected.content_type)
+            .await?;
+        if let Some(thumbnail) = &inspected.thumbnail {
+            state
+                .storage
+                .put(
+                    &thumbnail_key(&sha256),
+                    Bytes::from(thumbnail.clone()),
+                    "image/png",
+                )
+                .await?;
+        }
+    }
+
+    let (width, height) = inspected
+        .dimensions
+        .map_or((None, None), |(w, h)| (Some(w as i32), Some(h as i32)));
+    let inserted = sqlx::query_as::<_, Asset>(
+        "INSERT INTO assets
+         (project_id, sha256, file_name, content_type, size_bytes, width, height,
+          has_thumbnail, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+         ON CONFLICT (project_id, sha256) DO NOTHING
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(&sha256)
+    .bind(&file_name)
+    .bind(inspected.content_type)
+    .bind(bytes.len() as i64)
+    .bind(width)
+    .bind(height)
+    .bind(inspected.thumbnail.is_some())
+    .bind(auth.user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    match inserted {
+        Some(asset) => Ok((StatusCode::CREATED, Json(asset))),
+        // A concurrent upload of the same file won the insert
+        None => fetch_asset_by_hash(&state, project_id, &sha256)
+            .await?
+            .map(|asset| (StatusCode::OK, Json(asset)))
+            .ok_or_else(|| AppError::Conflict("Asset was deleted during upload".into())),
+    }
+}
+
+/// List a project's assets, newest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/assets",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Assets of the project", body = Vec<Asset>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_assets(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<Asset>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let assets = sqlx::query_as::<_, Asset>(
+        "SELECT * FROM assets WHERE project_id = $1 ORDER BY created_at DESC, id",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(assets))
+}
+
+/// Download an asset's bytes
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/assets/{asset_id}/content",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("asset_id" = Uuid, Path, description = "Asset UUID"),
+    ),
+    responses(
+        (status = 200, description = "The file, served with its stored content type"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or asset not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn asset_content(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, asset_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let asset = fetch_asset(&state, project_id, asset_id).await?;
+
+    let bytes = state
+        .storage
+        .get(&object_key(&asset.sha256))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Content of asset {asset_id} is missing")))?;
+    Ok(blob_response(bytes, &asset.content_type))
+}
+
+/// Download a PNG preview of an image asset
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/assets/{asset_id}/thumbnail",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("asset_id" = Uuid, Path, description = "Asset UUID"),
+    ),
+    responses(
+        (status = 200, description = "PNG thumbnail"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or asset not found, or the asset has no thumbnail"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn asset_thumbnail(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, asset_id)): Path<(Uuid, Uuid)>,
+) -> Result<Response> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let asset = fetch_asset(&state, project_id, asset_id).await?;
+    if !asset.has_thumbnail {
+        return Err(AppError::NotFound(format!(
+            "Asset {asset_id} has no thumbnail"
+        )));
+    }
+
+    let bytes = state
+        .storage
+        .get(&thumbnail_key(&asset.sha256))
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Thumbnail of asset {asset_id} is missing")))?;
+    Ok(blob_response(bytes, "image/png"))
+}
+
+/// Delete an asset and drop it from every node that references it
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/assets/{asset_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("asset_id" = Uuid, Path, description = "Asset UUID"),
+    ),
+    responses(
+        (status = 200, description = "Asset deleted; `nodesUpdated` counts nodes that lost the reference"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or asset not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_asset(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, asset_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Value>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let sha256: String =
+        sqlx::query_scalar("SELECT sha256 FROM assets WHERE project_id = $1 AND id = $2")
+            .bind(project_id)
+            .bind(asset_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Asset {asset_id} not found")))?;
+    lock_blob(&mut tx, &sha256).await?;
+    let deleted = sqlx::query("DELETE FROM assets WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(asset_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        return Err(AppError::NotFound(format!("Asset {asset_id} not found")));
+    }
+    let nodes_updated = sqlx::query(
+        "UPDATE canvas_nodes SET asset_ids = asset_ids - $2::text
+         WHERE project_id = $1 AND asset_ids ? $2::text",
+    )
+    .bind(project_id)
+    .bind(asset_id.to_string())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    delete_unreferenced_blobs(&state, &mut tx, &[sha256]).await?;
+    tx.commit().await?;
+
+    Ok(Json(
+        json!({ "message": "Asset deleted", "nodesUpdated": nodes_updated }),
+    ))
+}
+
+/// Serialize uploads and deletes of the same content until the transaction
+/// ends, so whether an object is still referenced cannot change under them
+async fn lock_blob(conn: &mut PgConnection, sha256: &str) -> Result<()> {
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+        .bind(sha256)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Take the blob locks of several hashes in a fixed order, so two callers
+/// locking overlapping sets cannot deadlock
+pub(crate) async fn lock_blobs(conn: &mut PgConnection, hashes: &mut Vec<String>) -> Result<()> {
+    hashes.sort();
+    hashes.dedup();
+    for sha256 in hashes.iter() {
+        lock_blob(&mut *conn, sha256).await?;
+    }
+    Ok(())
+}
+
+/// Delete the stored objects of hashes no asset row references any more.
+/// Callers hold the blob locks and run this in the transaction that removed
+/// the rows, so no upload can start sharing an object meanwhile. A failed
+/// storage delete only leaves wasted space and is logged.
+pub(crate) async fn delete_unreferenced_blobs(
+    state: &AppState,
+    conn: &mut PgConnection,
+    hashes: &[String],
+) -> Result<()> {
+    let unreferenced: Vec<String> = sqlx::query_scalar(
+        "SELECT h FROM UNNEST($1::text[]) AS h
+         WHERE NOT EXISTS (SELECT 1 FROM assets WHERE sha256 = h)",
+    )
+    .bind(hashes)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for sha256 in &unreferenced {
+        for key in [object_key(sha256), thumbnail_key(sha256)] {
+            if let Err(e) = state.storage.delete(&key).await {
+                tracing::warn!("Failed to delete stored object {key}: {e:?}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reject ids that are not assets of the project
+pub(crate) async fn ensure_assets_exist<'e, E>(
+    executor: E,
+    project_id: Uuid,
+    asset_ids: &[Uuid],
+) -> Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    if asset_ids.is_empty() {
+        return Ok(());
+    }
+
+    let missing: Vec<Uuid> = sqlx::query_scalar(
+        "SELECT DISTINCT id FROM UNNEST($2::uuid[]) AS ids(id)
+         WHERE NOT EXISTS (
+             SELECT 1 FROM assets a WHERE a.project_id = $1 AND a.id = ids.id
+         )",
+    )
+    .bind(project_id)
+    .bind(asset_ids)
+    .fetch_all(executor)
+    .await?;
+
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.iter().map(Uuid::to_string).collect();
+        return Err(AppError::Validation(format!(
+            "Unknown asset(s): {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Pull the `file` field out of the form, enforcing the size limit while streaming
+async fn read_file_field(
+    multipart: &mut Multipart,
+    max_bytes: usize,
+) -> Result<(String, Option<String>, Bytes)> {
+    let invalid = |e: axum::extract::multipart::MultipartError| {
+        AppError::Validation(format!("Invalid multipart body: {e}"))
+    };
+
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = field
+            .file_name()
+            .map(|name| {
+                name.rsplit(['/', '\\'])
+                    .next()
+                    .unwrap_or(name)
+                    .trim()
+                    .to_string()
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "upload".into());
+        let declared_type = field.content_type().map(str::to_string);
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(invalid)? {
+            if bytes.len() + chunk.len() > max_bytes {
+                return Err(AppError::Validation(format!(
+                    "File exceeds the {max_bytes} byte limit"
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        if bytes.is_empty() {
+            return Err(AppError::Validation("File is empty".into()));
+        }
+        return Ok((file_name, declared_type, bytes.into()));
+    }
+    Err(AppError::Validation(
+        "Missing multipart field 'file'".into(),
+    ))
+}
+
+/// Identify the file from its bytes and thumbnail raster images
+fn inspect(bytes: &[u8]) -> Result<Inspected> {
+    let format = image::guess_format(bytes).ok();
+    let content_type = match format {
+        Some(ImageFormat::Png) => "image/png",
+        Some(ImageFormat::Jpeg) => "image/jpeg",
+        Some(ImageFormat::Gif) => "image/gif",
+        Some(ImageFormat::WebP) => "image/webp",
+        _ if bytes.starts_with(b"%PDF-") => "application/pdf",
+        _ if is_svg(bytes) => "image/svg+xml",
+        _ => {
+            return Err(AppError::Validation(
+                "Unsupported file type; expected PNG, JPEG, GIF, WebP, SVG or PDF".into(),
+            ))
+        }
+    };
+    let Some(format) = format else {
+        return Ok(Inspected {
+            content_type,
+            dimensions: None,
+            thumbnail: None,
+        });
+    };
+
+    let mut reader = image::io::Reader::with_format(Cursor::new(bytes), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DECODE_DIMENSION);
+    limits.max_image_height = Some(MAX_DECODE_DIMENSION);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|e| AppError::Validation(format!("Image could not be decoded: {e}")))?;
+
+    let mut thumbnail = Cursor::new(Vec::new());
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .write_to(&mut thumbnail, ImageOutputFormat::Png)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Thumbnail encoding failed: {e}")))?;
+
+    Ok(Inspected {
+        content_type,
+        dimensions: Some((image.width(), image.height())),
+        thumbnail: Some(thumbnail.into_inner()),
+    })
+}
+
+/// SVG has no magic number; look for the root element near the start
+fn is_svg(bytes: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    (head.starts_with("<?xml") || head.starts_with("<svg") || head.starts_with("<!--"))
+        && head.contains("<svg")
+}
+
+fn object_key(sha256: &str) -> String {
+    format!("assets/{}/{sha256}", &sha256[..2])
+}
+
+fn thumbnail_key(sha256: &str) -> String {
+    format!("thumbnails/{}/{sha256}.png", &sha256[..2])
+}
+
+/// Serve stored bytes without letting browsers reinterpret them; SVGs may
+/// carry scripts, so they are sandboxed
+fn blob_response(bytes: Bytes, content_type: &str) -> Response {
+    let mut response = bytes.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_str(content_type)
+            .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("private, max-age=31536000, immutable"),
+    );
+    if content_type == "image/svg+xml" {
+        headers.insert(
+            header::CONTENT_SECURITY_POLICY,
+            HeaderValue::from_static("default-src 'none'; style-src 'unsafe-inline'; sandbox"),
+        );
+    }
+    response
+}
+
+async fn fetch_asset(state: &AppState, project_id: Uuid, asset_id: Uuid) -> Result<Asset> {
+    sqlx::query_as::<_, Asset>("SELECT * FROM assets WHERE project_id = $1 AND id = $2")
+        .bind(project_id)
+        .bind(asset_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Asset {asset_id} not found")))
+}
+
+async fn fetch_asset_by_hash(
+    state: &AppState,
+    project_id: Uuid,
+    sha256: &str,
+) -> Result<Option<Asset>> {
+    Ok(
+        sqlx::query_as::<_, Asset>("SELECT * FROM assets WHERE project_id = $1 AND sha256 = $2")
+            .bind(project_id)
+            .bind(sha256)
+            .fetch_optional(&state.db)
+            .await?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identifies_files_by_content() {
+        let mut png = Cursor::new(Vec::new());
+        image::DynamicImage::new_rgb8(600, 300)
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        let inspected = inspect(png.get_ref()).unwrap();
+        assert_eq!(inspected.content_type, "image/png");
+        assert_eq!(inspected.dimensions, Some((600, 300)));
+        let thumbnail = image::load_from_memory(&inspected.thumbnail.unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+
+        let svg = inspect(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>");
+        assert_eq!(svg.unwrap().content_type, "image/svg+xml");
+        assert_eq!(
+            inspect(b"%PDF-1.7\n").unwrap().content_type,
+            "application/pdf"
+        );
+        assert!(inspect(b"#!/bin/sh\nrm -rf /").is_err());
+    }
+}
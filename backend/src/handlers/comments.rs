@@ -0,0 +1,510 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::nodes::verify_project_owner,
+    middleware::auth::AuthUser,
+    models::{
+        canvas_node::NodeType,
+        comment::{
+            Comment, CommentBodyRequest, CommentResponse, CommentThread, CommentThreadResponse,
+            CreateThreadRequest, ListThreadsQuery, MentionedUser, UpdateThreadRequest,
+        },
+    },
+    state::AppState,
+};
+
+/// List comment threads of a project, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/threads",
+    params(("project_id" = Uuid, Path, description = "Project UUID"), ListThreadsQuery),
+    responses(
+        (status = 200, description = "Threads on live nodes with their comments", body = Vec<CommentThreadResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_threads(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<ListThreadsQuery>,
+) -> Result<Json<Vec<CommentThreadResponse>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut conn = state.db.acquire().await?;
+    let threads = fetch_threads(&mut conn, project_id, None, &query).await?;
+    Ok(Json(threads))
+}
+
+/// Start a thread on a node or on an element inside a design node
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/threads",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    request_body = CreateThreadRequest,
+    responses(
+        (status = 201, description = "Thread created", body = CommentThreadResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+        (status = 422, description = "Empty body, or a selector on a non-design node"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_thread(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Json(req): Json<CreateThreadRequest>,
+) -> Result<(StatusCode, Json<CommentThreadResponse>)> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let body = comment_body(&req.body)?;
+
+    let (node_id, node_type): (Uuid, NodeType) = sqlx::query_as(
+        "SELECT id, node_type FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(&req.client_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Node '{}' not found", req.client_id)))?;
+
+    let selector = match req.selector.as_deref().map(str::trim) {
+        None => None,
+        Some("") => return Err(AppError::Validation("selector must not be empty".into())),
+        Some(_) if !matches!(node_type, NodeType::Design) => {
+            return Err(AppError::Validation(
+                "Element threads are only supported on design nodes".into(),
+            ))
+        }
+        Some(selector) => Some(selector),
+    };
+
+    let mut tx = state.db.begin().await?;
+    let thread_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO comment_threads (project_id, node_id, selector, created_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id",
+    )
+    .bind(project_id)
+    .bind(node_id)
+    .bind(selector)
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_comment(&mut tx, project_id, thread_id, auth.user_id, body).await?;
+    let thread = fetch_thread(&mut tx, project_id, thread_id).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(thread)))
+}
+
+/// Resolve or reopen a thread
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/threads/{thread_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("thread_id" = Uuid, Path, description = "Thread UUID"),
+    ),
+    request_body = UpdateThreadRequest,
+    responses(
+        (status = 200, description = "Updated thread", body = CommentThreadResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Thread not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_thread(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, thread_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateThreadRequest>,
+) -> Result<Json<CommentThreadResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    // Resolving an already resolved thread keeps the original resolver
+    sqlx::query(
+        "UPDATE comment_threads SET
+            resolved_at = CASE WHEN $3 THEN COALESCE(resolved_at, NOW()) END,
+            resolved_by = CASE WHEN $3 AND resolved_at IS NOT NULL THEN resolved_by
+                               WHEN $3 THEN $4 END
+         WHERE id = $2 AND project_id = $1",
+    )
+    .bind(project_id)
+    .bind(thread_id)
+    .bind(req.resolved)
+    .bind(auth.user_id)
+    .execute(&state.db)
+    .await?;
+
+    let mut conn = state.db.acquire().await?;
+    let thread = fetch_thread(&mut conn, project_id, thread_id).await?;
+    Ok(Json(thread))
+}
+
+/// Reply to a thread
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/threads/{thread_id}/comments",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("thread_id" = Uuid, Path, description = "Thread UUID"),
+    ),
+    request_body = CommentBodyRequest,
+    responses(
+        (status = 201, description = "Comment added; returns the whole thread", body = CommentThreadResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Thread not found"),
+        (status = 422, description = "Empty body"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn add_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, thread_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<CommentBodyRequest>,
+) -> Result<(StatusCode, Json<CommentThreadResponse>)> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let body = comment_body(&req.body)?;
+
+    let mut tx = state.db.begin().await?;
+    let rows = sqlx::query(
+        "UPDATE comment_threads SET updated_at = NOW()
+         WHERE id = $2 AND project_id = $1
+           AND EXISTS (
+               SELECT 1 FROM canvas_nodes n
+               WHERE n.id = comment_threads.node_id AND n.deleted_at IS NULL
+           )",
+    )
+    .bind(project_id)
+    .bind(thread_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if rows == 0 {
+        return Err(AppError::NotFound(format!("Thread {thread_id} not found")));
+    }
+    insert_comment(&mut tx, project_id, thread_id, auth.user_id, body).await?;
+    let thread = fetch_thread(&mut tx, project_id, thread_id).await?;
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(thread)))
+}
+
+/// Edit one of your own comments
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/comments/{comment_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("comment_id" = Uuid, Path, description = "Comment UUID"),
+    ),
+    request_body = CommentBodyRequest,
+    responses(
+        (status = 200, description = "Comment updated; returns the whole thread", body = CommentThreadResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Comment was written by someone else"),
+        (status = 404, description = "Comment not found"),
+        (status = 422, description = "Empty body"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, comment_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<CommentBodyRequest>,
+) -> Result<Json<CommentThreadResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let body = comment_body(&req.body)?;
+
+    let mut tx = state.db.begin().await?;
+    let thread_id = own_comment_thread(&mut tx, project_id, comment_id, auth.user_id).await?;
+    sqlx::query("UPDATE comments SET body = $2, edited_at = NOW() WHERE id = $1")
+        .bind(comment_id)
+        .bind(body)
+        .execute(&mut *tx)
+        .await?;
+    save_mentions(&mut tx, project_id, comment_id, body).await?;
+    let thread = fetch_thread(&mut tx, project_id, thread_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(thread))
+}
+
+/// Delete one of your own comments; a thread left empty is deleted with it
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/comments/{comment_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("comment_id" = Uuid, Path, description = "Comment UUID"),
+    ),
+    responses(
+        (status = 200, description = "Comment deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Comment was written by someone else"),
+        (status = 404, description = "Comment not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, comment_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<Value>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let thread_id = own_comment_thread(&mut tx, project_id, comment_id, auth.user_id).await?;
+    sqlx::query("DELETE FROM comments WHERE id = $1")
+        .bind(comment_id)
+        .execute(&mut *tx)
+        .await?;
+    let thread_deleted = sqlx::query(
+        "DELETE FROM comment_threads
+         WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM comments WHERE thread_id = $1)",
+    )
+    .bind(thread_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+    tx.commit().await?;
+
+    Ok(Json(
+        json!({ "message": "Comment deleted", "threadDeleted": thread_deleted }),
+    ))
+}
+
+fn comment_body(body: &str) -> Result<&str> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(AppError::Validation(
+            "Comment body must not be empty".into(),
+        ));
+    }
+    Ok(body)
+}
+
+/// Thread of a comment in the project, if the caller wrote it
+async fn own_comment_thread(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    comment_id: Uuid,
+    user_id: Uuid,
+) -> Result<Uuid> {
+    let (thread_id, author_id): (Uuid, Option<Uuid>) = sqlx::query_as(
+        "SELECT c.thread_id, c.author_id FROM comments c
+         JOIN comment_threads t ON t.id = c.thread_id
+         WHERE c.id = $1 AND t.project_id = $2
+         FOR UPDATE OF c",
+    )
+    .bind(comment_id)
+    .bind(project_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Comment {comment_id} not found")))?;
+
+    if author_id != Some(user_id) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(thread_id)
+}
+
+async fn insert_comment(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    thread_id: Uuid,
+    author_id: Uuid,
+    body: &str,
+) -> Result<()> {
+    let comment_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO comments (thread_id, author_id, body) VALUES ($1, $2, $3) RETURNING id",
+    )
+    .bind(thread_id)
+    .bind(author_id)
+    .bind(body)
+    .fetch_one(&mut *conn)
+    .await?;
+    save_mentions(conn, project_id, comment_id, body).await
+}
+
+/// Replace a comment's mentions with the project members named in its body.
+/// Handles that match nobody stay plain text.
+async fn save_mentions(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    comment_id: Uuid,
+    body: &str,
+) -> Result<()> {
+    sqlx::query("DELETE FROM comment_mentions WHERE comment_id = $1")
+        .bind(comment_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let handles = mention_handles(body);
+    if handles.is_empty() {
+        return Ok(());
+    }
+    // Projects are not shared yet, so the owner is the only member
+    sqlx::query(
+        "INSERT INTO comment_mentions (comment_id, user_id)
+         SELECT $2, u.id FROM projects p
+         JOIN users u ON u.id = p.user_id
+         WHERE p.id = $1
+           AND (LOWER(u.email) = ANY($3) OR LOWER(u.display_name) = ANY($3))",
+    )
+    .bind(project_id)
+    .bind(comment_id)
+    .bind(&handles)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Lower-cased handles written as `@name` or `@email`; an `@` inside a
+/// word (as in a bare email address) does not start a mention
+fn mention_handles(body: &str) -> Vec<String> {
+    let mut handles = Vec::new();
+    let mut prev: Option<char> = None;
+    for (i, c) in body.char_indices() {
+        if c == '@' && !prev.is_some_and(char::is_alphanumeric) {
+            let rest = &body[i + 1..];
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || "._-+@".contains(c)))
+                .unwrap_or(rest.len());
+            let handle = rest[..end].trim_end_matches(['.', '-']);
+            if !handle.is_empty() {
+                handles.push(handle.to_lowercase());
+            }
+        }
+        prev = Some(c);
+    }
+    handles.sort_unstable();
+    handles.dedup();
+    handles
+}
+
+async fn fetch_thread(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    thread_id: Uuid,
+) -> Result<CommentThreadResponse> {
+    let filter = ListThreadsQuery {
+        client_id: None,
+        resolved: None,
+    };
+    fetch_threads(conn, project_id, Some(thread_id), &filter)
+        .await?
+        .pop()
+        .ok_or_else(|| AppError::NotFound(format!("Thread {thread_id} not found")))
+}
+
+/// Threads on live nodes with their comments and mentions
+async fn fetch_threads(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    thread_id: Option<Uuid>,
+    filter: &ListThreadsQuery,
+) -> Result<Vec<CommentThreadResponse>> {
+    let mut qb: QueryBuilder<Postgres> = QueryBuilder::new(
+        "SELECT t.id, n.client_id, t.selector, t.created_by, t.resolved_at, t.resolved_by,
+                t.created_at, t.updated_at
+         FROM comment_threads t
+         JOIN canvas_nodes n ON n.id = t.node_id AND n.deleted_at IS NULL
+         WHERE t.project_id = ",
+    );
+    qb.push_bind(project_id);
+    if let Some(thread_id) = thread_id {
+        qb.push(" AND t.id = ").push_bind(thread_id);
+    }
+    if let Some(client_id) = &filter.client_id {
+        qb.push(" AND n.client_id = ").push_bind(client_id);
+    }
+    if let Some(resolved) = filter.resolved {
+        qb.push(" AND (t.resolved_at IS NOT NULL) = ")
+            .push_bind(resolved);
+    }
+    qb.push(" ORDER BY t.created_at, t.id");
+    let threads = qb
+        .build_query_as::<CommentThread>()
+        .fetch_all(&mut *conn)
+        .await?;
+    if threads.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids: Vec<Uuid> = threads.iter().map(|t| t.id).collect();
+    let comments = sqlx::query_as::<_, Comment>(
+        "SELECT c.id, c.thread_id, c.author_id, u.display_name AS author_name, c.body,
+                c.edited_at, c.created_at
+         FROM comments c
+         LEFT JOIN users u ON u.id = c.author_id
+         WHERE c.thread_id = ANY($1)
+         ORDER BY c.created_at, c.id",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mentions = sqlx::query_as::<_, MentionedUser>(
+        "SELECT m.comment_id, u.id AS user_id, u.display_name
+         FROM comment_mentions m
+         JOIN comments c ON c.id = m.comment_id
+         JOIN users u ON u.id = m.user_id
+         WHERE c.thread_id = ANY($1)
+         ORDER BY u.display_name",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut mentions_by_comment: HashMap<Uuid, Vec<MentionedUser>> = HashMap::new();
+    for m in mentions {
+        mentions_by_comment.entry(m.comment_id).or_default().push(m);
+    }
+    let mut comments_by_thread: HashMap<Uuid, Vec<CommentResponse>> = HashMap::new();
+    for c in comments {
+        comments_by_thread
+            .entry(c.thread_id)
+            .or_default()
+            .push(CommentResponse {
+                id: c.id,
+                author_id: c.author_id,
+                author_name: c.author_name,
+                body: c.body,
+                mentions: mentions_by_comment.remove(&c.id).unwrap_or_default(),
+                edited_at: c.edited_at,
+                created_at: c.created_at,
+            });
+    }
+
+    Ok(threads
+        .into_iter()
+        .map(|t| CommentThreadResponse {
+            comments: comments_by_thread.remove(&t.id).unwrap_or_default(),
+            id: t.id,
+            client_id: t.client_id,
+            selector: t.selector,
+            created_by: t.created_by,
+            resolved: t.resolved_at.is_some(),
+            resolved_at: t.resolved_at,
+            resolved_by: t.resolved_by,
+            created_at: t.created_at,
+            updated_at: t.updated_at,
+        })
+        .collect())
+}
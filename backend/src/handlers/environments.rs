@@ -0,0 +1,299 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{
+    dotenv,
+    environments::{EnvCatalog, EnvNode, EnvValue},
+    error::{AppError, Result},
+    handlers::nodes::{fetch_node_response, live_node_id, verify_project_owner},
+    handlers::{locks, revisions, secrets as secret_reveals},
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::canvas_node::{CanvasNodeResponse, NodeType},
+    models::environment::{
+        DotenvExportQuery, DotenvImportRequest, EffectiveEnvQuery, EffectiveEnvironment,
+    },
+    models::node_payload::EnvConfig,
+    secrets,
+    state::AppState,
+};
+
+#[derive(sqlx::FromRow)]
+struct EnvRow {
+    client_id: String,
+    title: String,
+    env_vars: Value,
+    payload: Option<Value>,
+    updated_at: DateTime<Utc>,
+}
+
+/// Resolve the variables a node sees in one environment. Env nodes joined to
+/// it by an `env_binding` connection, in either direction, are bound to it,
+/// and an env node is bound to itself. Other kinds of connection to an env
+/// node (data flow, navigation) do not bind it.
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/environment",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        EffectiveEnvQuery,
+    ),
+    responses(
+        (status = 200, description = "Effective variables with their sources, conflicts, missing required variables and broken bases", body = EffectiveEnvironment),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn effective_environment(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(query): Query<EffectiveEnvQuery>,
+) -> Result<Json<EffectiveEnvironment>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    live_node_id(&state.db, project_id, &client_id).await?;
+
+    let rows = sqlx::query_as::<_, EnvRow>(
+        "SELECT client_id, title, env_vars, payload, updated_at FROM canvas_nodes
+         WHERE project_id = $1 AND node_type = 'env' AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+    let other_nodes: HashSet<String> = sqlx::query_scalar(
+        "SELECT client_id FROM canvas_nodes
+         WHERE project_id = $1 AND node_type <> 'env' AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .collect();
+    let mut bound: Vec<String> = sqlx::query_scalar(
+        "SELECT CASE WHEN from_client_id = $2 THEN to_client_id ELSE from_client_id END
+         FROM node_connections
+         WHERE project_id = $1 AND kind = 'env_binding'
+           AND $2 IN (from_client_id, to_client_id)",
+    )
+    .bind(project_id)
+    .bind(&client_id)
+    .fetch_all(&state.db)
+    .await?;
+    bound.push(client_id.clone());
+
+    let secret = &state.cfg.api_key_encryption_secret;
+    let mut env_nodes = HashMap::with_capacity(rows.len());
+    for row in rows {
+        let config: EnvConfig = row
+            .payload
+            .and_then(|p| serde_json::from_value(p).ok())
+            .unwrap_or_default();
+        let stored: HashMap<String, String> =
+            serde_json::from_value(row.env_vars).unwrap_or_default();
+        let mut vars = HashMap::with_capacity(stored.len());
+        for (name, value) in stored {
+            let secret_var = secrets::is_sealed(&value);
+            let value = EnvValue {
+                value: secrets::open(&value, secret)?,
+                secret: secret_var,
+            };
+            vars.insert(name, value);
+        }
+        let node = EnvNode {
+            client_id: row.client_id.clone(),
+            title: row.title,
+            environment: config.environment,
+            base: config.base,
+            required: config.required,
+            vars,
+            updated_at: row.updated_at,
+        };
+        env_nodes.insert(row.client_id, node);
+    }
+
+    let catalog = EnvCatalog {
+        env_nodes,
+        other_nodes,
+    };
+    Ok(Json(catalog.resolve(&client_id, query.environment, &bound)))
+}
+
+/// Load a `.env` file into an env node's variables
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/env/import",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Env node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    request_body = DotenvImportRequest,
+    responses(
+        (status = 200, description = "Updated node", body = CanvasNodeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 409, description = "Another session holds the edit lock"),
+        (status = 422, description = "Not an env node, or the file cannot be parsed"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn import_dotenv(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Json(req): Json<DotenvImportRequest>,
+) -> Result<Json<CanvasNodeResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let imported =
+        dotenv::parse(&req.content).map_err(|e| AppError::Validation(format!(".env {e}")))?;
+
+    let mut tx = state.db.begin().await?;
+    let locked = revisions::lock_node(&mut tx, project_id, &client_id).await?;
+    locks::ensure_unlocked(&mut tx, locked.id, &client_id, lock_token).await?;
+    ensure_env_node(&client_id, &locked.node_type)?;
+
+    let stored: HashMap<String, String> = serde_json::from_value(
+        sqlx::query_scalar("SELECT env_vars FROM canvas_nodes WHERE id = $1")
+            .bind(locked.id)
+            .fetch_one(&mut *tx)
+            .await?,
+    )
+    .unwrap_or_default();
+
+    // Merged variables keep their stored values, secret or not
+    let mut incoming: HashMap<String, String> = if req.replace.unwrap_or(false) {
+        HashMap::new()
+    } else {
+        stored
+            .keys()
+            .map(|k| (k.clone(), secrets::MASK.to_string()))
+            .collect()
+    };
+    incoming.extend(imported);
+    let mut marked: Vec<String> = stored
+        .iter()
+        .filter(|(k, v)| secrets::is_sealed(v) && incoming.contains_key(*k))
+        .map(|(k, _)| k.clone())
+        .collect();
+    marked.extend(req.secret_env_vars.unwrap_or_default());
+
+    let secret = &state.cfg.api_key_encryption_secret;
+    let env_vars = secrets::seal_env_vars(&incoming, Some(&marked), &stored, secret)?;
+    sqlx::query("UPDATE canvas_nodes SET env_vars = $2 WHERE id = $1")
+        .bind(locked.id)
+        .bind(serde_json::to_value(env_vars).unwrap_or_default())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    let node = fetch_node_response(&state, project_id, &client_id).await?;
+    Ok(Json(node))
+}
+
+/// Download an env node's variables as a `.env` file, sorted by name
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/env/export",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Env node ID"),
+        DotenvExportQuery,
+    ),
+    responses(
+        (status = 200, description = "The `.env` or `.env.example` file", content_type = "text/plain"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 422, description = "Not an env node"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn export_dotenv(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(query): Query<DotenvExportQuery>,
+) -> Result<Response> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let (node_id, node_type, title, env_vars) =
+        sqlx::query_as::<_, (Uuid, NodeType, String, Value)>(
+            "SELECT id, node_type, title, env_vars FROM canvas_nodes
+             WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+        )
+        .bind(project_id)
+        .bind(&client_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))?;
+    ensure_env_node(&client_id, &node_type)?;
+
+    let example = query.example.unwrap_or(false);
+    let include_secrets = query.include_secrets.unwrap_or(false) && !example;
+    let mut vars: BTreeMap<String, String> = serde_json::from_value(env_vars).unwrap_or_default();
+    let mut revealed = Vec::new();
+    let mut withheld = false;
+    for (name, value) in vars.iter_mut() {
+        if !secrets::is_sealed(value) {
+            continue;
+        }
+        if include_secrets {
+            *value = secrets::open(value, &state.cfg.api_key_encryption_secret)?;
+            revealed.push(format!("env.{name}"));
+        } else {
+            value.clear();
+            withheld = true;
+        }
+    }
+    secret_reveals::record_reveals(
+        &state,
+        auth.user_id,
+        project_id,
+        (node_id, &client_id),
+        &revealed,
+    )
+    .await?;
+
+    let mut header = title;
+    if withheld && !example {
+        header.push_str("\nSecret values are left empty");
+    }
+    let text = dotenv::render(
+        &header,
+        vars.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+        example,
+    );
+    let file_name = if example { ".env.example" } else { ".env" };
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{file_name}\""),
+            ),
+        ],
+        text,
+    )
+        .into_response())
+}
+
+fn ensure_env_node(client_id: &str, node_type: &NodeType) -> Result<()> {
+    match node_type {
+        NodeType::Env => Ok(()),
+        _ => Err(AppError::Validation(format!(
+            "Node '{client_id}' is not an env node"
+        ))),
+    }
+}
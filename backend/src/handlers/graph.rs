@@ -0,0 +1,215 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    graph::Graph,
+    handlers::nodes::{fetch_live_connections, verify_project_owner},
+    middleware::auth::AuthUser,
+    models::canvas_node::ConnectionKind,
+    models::graph::{
+        GraphAnalysis, GraphAnalysisQuery, RelatedNode, RelatedNodesQuery, ShortestPath,
+        ShortestPathQuery,
+    },
+    state::AppState,
+};
+
+/// Report cycles, a topological order, orphans and unreachable nodes
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/graph/analysis",
+    params(("project_id" = Uuid, Path, description = "Project UUID"), GraphAnalysisQuery),
+    responses(
+        (status = 200, description = "Graph analysis", body = GraphAnalysis),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+        (status = 422, description = "A root is not a live node, or an unknown connection kind"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn analyze_graph(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Query(params): Query<GraphAnalysisQuery>,
+) -> Result<Json<GraphAnalysis>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let (graph, connection_count) =
+        load_graph(&state, project_id, params.kinds.as_deref()).await?;
+
+    let roots: Option<Vec<String>> = params.roots.as_deref().map(|raw| {
+        raw.split(',')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(String::from)
+            .collect()
+    });
+    if let Some(unknown) = roots.iter().flatten().find(|r| !graph.contains(r)) {
+        return Err(AppError::Validation(format!(
+            "Unknown root node '{unknown}'"
+        )));
+    }
+
+    let cycles = graph.cycles();
+    Ok(Json(GraphAnalysis {
+        node_count: graph.node_count(),
+        connection_count,
+        is_dag: cycles.is_empty(),
+        cycles,
+        topological_order: graph.topological_order(),
+        orphans: graph.orphans(),
+        unreachable: graph.unreachable(roots.as_deref()),
+    }))
+}
+
+/// Nodes that lead to a node through connections, nearest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/graph/nodes/{client_id}/ancestors",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        RelatedNodesQuery,
+    ),
+    responses(
+        (status = 200, description = "Ancestors with their distance", body = Vec<RelatedNode>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 422, description = "Unknown connection kind"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn node_ancestors(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(params): Query<RelatedNodesQuery>,
+) -> Result<Json<Vec<RelatedNode>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let (graph, _) = load_graph(&state, project_id, params.kinds.as_deref()).await?;
+    ensure_in_graph(&graph, &client_id)?;
+
+    Ok(Json(to_related(
+        graph.ancestors(&client_id, params.max_depth),
+    )))
+}
+
+/// Nodes reachable from a node through connections, nearest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/graph/nodes/{client_id}/descendants",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        RelatedNodesQuery,
+    ),
+    responses(
+        (status = 200, description = "Descendants with their distance", body = Vec<RelatedNode>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 422, description = "Unknown connection kind"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn node_descendants(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(params): Query<RelatedNodesQuery>,
+) -> Result<Json<Vec<RelatedNode>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let (graph, _) = load_graph(&state, project_id, params.kinds.as_deref()).await?;
+    ensure_in_graph(&graph, &client_id)?;
+
+    Ok(Json(to_related(
+        graph.descendants(&client_id, params.max_depth),
+    )))
+}
+
+/// Fewest-hop path between two nodes
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/graph/path",
+    params(("project_id" = Uuid, Path, description = "Project UUID"), ShortestPathQuery),
+    responses(
+        (status = 200, description = "Shortest path", body = ShortestPath),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found or no path between the nodes"),
+        (status = 422, description = "Unknown connection kind"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn shortest_path(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Query(params): Query<ShortestPathQuery>,
+) -> Result<Json<ShortestPath>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let (graph, _) = load_graph(&state, project_id, params.kinds.as_deref()).await?;
+    ensure_in_graph(&graph, &params.from)?;
+    ensure_in_graph(&graph, &params.to)?;
+
+    let path = graph
+        .shortest_path(&params.from, &params.to, params.undirected.unwrap_or(false))
+        .ok_or_else(|| {
+            AppError::NotFound(format!("No path from '{}' to '{}'", params.from, params.to))
+        })?;
+
+    Ok(Json(ShortestPath {
+        hops: path.len() - 1,
+        path,
+    }))
+}
+
+/// Live nodes and connections of the requested kinds, plus the connection count
+async fn load_graph(
+    state: &AppState,
+    project_id: Uuid,
+    kinds: Option<&str>,
+) -> Result<(Graph, usize)> {
+    let kinds = parse_kinds(kinds)?;
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT client_id FROM canvas_nodes
+         WHERE project_id = $1 AND deleted_at IS NULL
+         ORDER BY created_at, id",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+    let edges = fetch_live_connections(state, project_id, Some(&kinds)).await?;
+
+    Ok((Graph::new(ids, &edges), edges.len()))
+}
+
+fn parse_kinds(raw: Option<&str>) -> Result<Vec<ConnectionKind>> {
+    let Some(raw) = raw else {
+        return Ok(vec![ConnectionKind::DataFlow, ConnectionKind::DependsOn]);
+    };
+    raw.split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|k| {
+            serde_json::from_value(serde_json::Value::String(k.to_string()))
+                .map_err(|_| AppError::Validation(format!("Unknown connection kind '{k}'")))
+        })
+        .collect()
+}
+
+fn ensure_in_graph(graph: &Graph, client_id: &str) -> Result<()> {
+    if graph.contains(client_id) {
+        Ok(())
+    } else {
+        Err(AppError::NotFound(format!("Node '{client_id}' not found")))
+    }
+}
+
+fn to_related(nodes: Vec<(String, usize)>) -> Vec<RelatedNode> {
+    nodes
+        .into_iter()
+        .map(|(client_id, depth)| RelatedNode { client_id, depth })
+        .collect()
+}
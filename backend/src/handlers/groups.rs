@@ -0,0 +1,582 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::{PgConnection, PgExecutor};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::locks,
+    handlers::nodes::{ensure_nodes_exist, verify_project_owner},
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::canvas_group::{
+        CanvasGroup, CanvasGroupResponse, CreateGroupRequest, DeleteGroupQuery,
+        MoveGroupRequest, SetGroupMembersRequest, UpdateGroupRequest,
+    },
+    state::AppState,
+};
+
+/// Ids of a group and all live groups nested inside it, for use in a CTE
+const GROUP_TREE: &str = "WITH RECURSIVE tree AS (
+         SELECT id FROM canvas_groups WHERE project_id = $1 AND id = $2
+         UNION ALL
+         SELECT g.id FROM canvas_groups g JOIN tree t ON g.parent_id = t.id
+         WHERE g.deleted_at IS NULL
+     )";
+
+/// List all groups of a project
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/groups",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Groups, outermost first", body = Vec<CanvasGroupResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_groups(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<CanvasGroupResponse>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let groups = fetch_groups(&state.db, project_id).await?;
+    Ok(Json(groups))
+}
+
+/// Create a group, optionally nested and pre-filled with nodes
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/groups",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    request_body = CreateGroupRequest,
+    responses(
+        (status = 201, description = "Group created", body = CanvasGroupResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or parent group not found"),
+        (status = 422, description = "nodeIds names an unknown node"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn create_group(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Json(req): Json<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<CanvasGroupResponse>)> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+
+    if let Some(parent_id) = req.parent_id {
+        fetch_group(&mut *tx, project_id, parent_id).await?;
+    }
+
+    let group = sqlx::query_as::<_, CanvasGroup>(
+        "INSERT INTO canvas_groups (project_id, parent_id, title, color, x, y, width, height)
+         VALUES ($1, $2, $3, COALESCE($4, '#64748b'), $5, $6, $7, $8)
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(req.parent_id)
+    .bind(&req.title)
+    .bind(req.color.as_deref())
+    .bind(req.x)
+    .bind(req.y)
+    .bind(req.width)
+    .bind(req.height)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let node_ids = req.node_ids.unwrap_or_default();
+    set_members(&mut tx, project_id, group.id, &node_ids).await?;
+
+    tx.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(group_to_response(group, node_ids))))
+}
+
+/// Update a group's title, color, geometry, collapsed state or parent
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/groups/{group_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("group_id" = Uuid, Path, description = "Group UUID"),
+    ),
+    request_body = UpdateGroupRequest,
+    responses(
+        (status = 200, description = "Updated group", body = CanvasGroupResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group or parent group not found"),
+        (status = 422, description = "New parent would create a cycle"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_group(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, group_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateGroupRequest>,
+) -> Result<Json<CanvasGroupResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let current = fetch_group(&mut *tx, project_id, group_id).await?;
+
+    let parent_id = if req.detach.unwrap_or(false) {
+        None
+    } else if let Some(parent_id) = req.parent_id {
+        fetch_group(&mut *tx, project_id, parent_id).await?;
+        let cycle: bool = sqlx::query_scalar(&format!(
+            "{GROUP_TREE} SELECT EXISTS (SELECT 1 FROM tree WHERE id = $3)"
+        ))
+        .bind(project_id)
+        .bind(group_id)
+        .bind(parent_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if cycle {
+            return Err(AppError::Validation(
+                "parentId: a group cannot be nested inside itself or its descendants".into(),
+            ));
+        }
+        Some(parent_id)
+    } else {
+        current.parent_id
+    };
+
+    let group = sqlx::query_as::<_, CanvasGroup>(
+        "UPDATE canvas_groups SET
+            parent_id = $3,
+            title     = COALESCE($4, title),
+            color     = COALESCE($5, color),
+            x         = COALESCE($6, x),
+            y         = COALESCE($7, y),
+            width     = COALESCE($8, width),
+            height    = COALESCE($9, height),
+            collapsed = COALESCE($10, collapsed)
+         WHERE project_id = $1 AND id = $2
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(group_id)
+    .bind(parent_id)
+    .bind(req.title.as_deref())
+    .bind(req.color.as_deref())
+    .bind(req.x)
+    .bind(req.y)
+    .bind(req.width)
+    .bind(req.height)
+    .bind(req.collapsed)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let node_ids = fetch_member_ids(&mut *tx, group_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(group_to_response(group, node_ids)))
+}
+
+/// Move a group together with its nested groups and all of their nodes
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/groups/{group_id}/move",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("group_id" = Uuid, Path, description = "Group UUID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    request_body = MoveGroupRequest,
+    responses(
+        (status = 200, description = "Number of groups and nodes moved"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 409, description = "Another session holds the edit lock on a node in the group"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn move_group(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, group_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<MoveGroupRequest>,
+) -> Result<Json<Value>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    fetch_group(&mut *tx, project_id, group_id).await?;
+
+    let groups_moved = sqlx::query(&format!(
+        "{GROUP_TREE}
+         UPDATE canvas_groups SET x = x + $3, y = y + $4
+         WHERE id IN (SELECT id FROM tree)"
+    ))
+    .bind(project_id)
+    .bind(group_id)
+    .bind(req.dx)
+    .bind(req.dy)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    let nodes_moved: Vec<String> = sqlx::query_scalar(&format!(
+        "{GROUP_TREE}
+         UPDATE canvas_nodes SET x = x + $3, y = y + $4
+         WHERE project_id = $1 AND deleted_at IS NULL
+           AND group_id IN (SELECT id FROM tree)
+         RETURNING client_id"
+    ))
+    .bind(project_id)
+    .bind(group_id)
+    .bind(req.dx)
+    .bind(req.dy)
+    .fetch_all(&mut *tx)
+    .await?;
+    locks::ensure_none_locked(&mut tx, project_id, &nodes_moved, lock_token).await?;
+
+    tx.commit().await?;
+
+    Ok(Json(json!({ "groupsMoved": groups_moved, "nodesMoved": nodes_moved.len() })))
+}
+
+/// Replace the nodes directly inside a group. Listed nodes leave whatever
+/// group they were in; nodes no longer listed move to the top level.
+#[utoipa::path(
+    put,
+    path = "/api/projects/{project_id}/groups/{group_id}/members",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("group_id" = Uuid, Path, description = "Group UUID"),
+    ),
+    request_body = SetGroupMembersRequest,
+    responses(
+        (status = 200, description = "Updated group", body = CanvasGroupResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 422, description = "nodeIds names an unknown node"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_group_members(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, group_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<SetGroupMembersRequest>,
+) -> Result<Json<CanvasGroupResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let group = fetch_group(&mut *tx, project_id, group_id).await?;
+
+    sqlx::query(
+        "UPDATE canvas_nodes SET group_id = NULL
+         WHERE project_id = $1 AND group_id = $2 AND NOT (client_id = ANY($3))",
+    )
+    .bind(project_id)
+    .bind(group_id)
+    .bind(&req.node_ids)
+    .execute(&mut *tx)
+    .await?;
+    set_members(&mut tx, project_id, group_id, &req.node_ids).await?;
+
+    let node_ids = fetch_member_ids(&mut *tx, group_id).await?;
+    tx.commit().await?;
+
+    Ok(Json(group_to_response(group, node_ids)))
+}
+
+/// Delete a group. By default its nodes and nested groups move up to the
+/// parent group; with `withContents=true` the group, its nested groups and
+/// their nodes all go to the trash, and restoring a node restores its group.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/groups/{group_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("group_id" = Uuid, Path, description = "Group UUID"),
+        DeleteGroupQuery,
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    responses(
+        (status = 200, description = "Group deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Group not found"),
+        (status = 409, description = "Another session holds the edit lock on a node to be trashed"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_group(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, group_id)): Path<(Uuid, Uuid)>,
+    Query(params): Query<DeleteGroupQuery>,
+) -> Result<Json<Value>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let group = fetch_group(&mut *tx, project_id, group_id).await?;
+
+    let trashed_nodes = if params.with_contents.unwrap_or(false) {
+        let trashed: Vec<String> = sqlx::query_scalar(&format!(
+            "{GROUP_TREE}
+             UPDATE canvas_nodes SET deleted_at = NOW()
+             WHERE project_id = $1 AND deleted_at IS NULL
+               AND group_id IN (SELECT id FROM tree)
+             RETURNING client_id"
+        ))
+        .bind(project_id)
+        .bind(group_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        locks::ensure_none_locked(&mut tx, project_id, &trashed, lock_token).await?;
+
+        // The groups are trashed rather than deleted so the trashed nodes
+        // keep their group_id for a later restore
+        sqlx::query(&format!(
+            "{GROUP_TREE}
+             UPDATE canvas_groups SET deleted_at = NOW()
+             WHERE id IN (SELECT id FROM tree)"
+        ))
+        .bind(project_id)
+        .bind(group_id)
+        .execute(&mut *tx)
+        .await?;
+        trashed.len()
+    } else {
+        sqlx::query("UPDATE canvas_groups SET parent_id = $2 WHERE parent_id = $1")
+            .bind(group_id)
+            .bind(group.parent_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE canvas_nodes SET group_id = $2 WHERE group_id = $1")
+            .bind(group_id)
+            .bind(group.parent_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM canvas_groups WHERE id = $1")
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await?;
+        0
+    };
+
+    tx.commit().await?;
+
+    Ok(Json(json!({ "message": "Group deleted", "trashedNodes": trashed_nodes })))
+}
+
+/// All groups of a project with their live member nodes, parents before children
+pub(crate) async fn fetch_groups<'e, E>(
+    executor: E,
+    project_id: Uuid,
+) -> Result<Vec<CanvasGroupResponse>>
+where
+    E: PgExecutor<'e> + Copy,
+{
+    let groups = sqlx::query_as::<_, CanvasGroup>(
+        "WITH RECURSIVE ordered AS (
+             SELECT g.*, 0 AS depth FROM canvas_groups g
+             WHERE g.project_id = $1 AND g.parent_id IS NULL AND g.deleted_at IS NULL
+             UNION ALL
+             SELECT g.*, o.depth + 1 FROM canvas_groups g JOIN ordered o ON g.parent_id = o.id
+             WHERE g.deleted_at IS NULL
+         )
+         SELECT * FROM ordered ORDER BY depth, created_at",
+    )
+    .bind(project_id)
+    .fetch_all(executor)
+    .await?;
+
+    let members: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT group_id, client_id FROM canvas_nodes
+         WHERE project_id = $1 AND group_id IS NOT NULL AND deleted_at IS NULL
+         ORDER BY created_at",
+    )
+    .bind(project_id)
+    .fetch_all(executor)
+    .await?;
+
+    let mut by_group: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (group_id, client_id) in members {
+        by_group.entry(group_id).or_default().push(client_id);
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|g| {
+            let node_ids = by_group.remove(&g.id).unwrap_or_default();
+            group_to_response(g, node_ids)
+        })
+        .collect())
+}
+
+async fn fetch_group<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: Uuid,
+    group_id: Uuid,
+) -> Result<CanvasGroup> {
+    sqlx::query_as::<_, CanvasGroup>(
+        "SELECT * FROM canvas_groups
+         WHERE project_id = $1 AND id = $2 AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(group_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Group {group_id} not found")))
+}
+
+/// Bring back the trashed groups of the given live nodes, with any trashed
+/// groups around them, so restored nodes land in the group they left with
+pub(crate) async fn restore_groups_of<'e, E: PgExecutor<'e>>(
+    executor: E,
+    project_id: Uuid,
+    client_ids: &[&str],
+) -> Result<()> {
+    sqlx::query(
+        "WITH RECURSIVE chain AS (
+             SELECT g.id, g.parent_id FROM canvas_groups g
+             JOIN canvas_nodes n ON n.group_id = g.id
+             WHERE n.project_id = $1 AND n.client_id = ANY($2) AND n.deleted_at IS NULL
+             UNION
+             SELECT g.id, g.parent_id FROM canvas_groups g JOIN chain c ON g.id = c.parent_id
+         )
+         UPDATE canvas_groups SET deleted_at = NULL
+         WHERE id IN (SELECT id FROM chain) AND deleted_at IS NOT NULL",
+    )
+    .bind(project_id)
+    .bind(client_ids)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn fetch_member_ids<'e, E: PgExecutor<'e>>(
+    executor: E,
+    group_id: Uuid,
+) -> Result<Vec<String>> {
+    let ids = sqlx::query_scalar(
+        "SELECT client_id FROM canvas_nodes
+         WHERE group_id = $1 AND deleted_at IS NULL
+         ORDER BY created_at",
+    )
+    .bind(group_id)
+    .fetch_all(executor)
+    .await?;
+    Ok(ids)
+}
+
+async fn set_members(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    group_id: Uuid,
+    node_ids: &[String],
+) -> Result<()> {
+    if node_ids.is_empty() {
+        return Ok(());
+    }
+    ensure_nodes_exist(&mut *conn, project_id, node_ids).await?;
+
+    sqlx::query(
+        "UPDATE canvas_nodes SET group_id = $2
+         WHERE project_id = $1 AND client_id = ANY($3) AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(group_id)
+    .bind(node_ids)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+fn group_to_response(group: CanvasGroup, node_ids: Vec<String>) -> CanvasGroupResponse {
+    CanvasGroupResponse {
+        id: group.id,
+        parent_id: group.parent_id,
+        title: group.title,
+        color: group.color,
+        x: group.x,
+        y: group.y,
+        width: group.width,
+        height: group.height,
+        collapsed: group.collapsed,
+        node_ids,
+        created_at: group.created_at,
+        updated_at: group.updated_at,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::trash::restore_node;
+    use crate::test_support::TestProject;
+
+    /// Trashing a group with its contents and restoring a member brings the
+    /// group back with that member in it
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn restoring_a_member_restores_its_trashed_group() {
+        let test = TestProject::new().await;
+        sqlx::query(
+            "INSERT INTO canvas_nodes
+             (project_id, client_id, node_type, title, description, x, y, width, height)
+             VALUES ($1, 'a', 'idea', 'A', '', 0, 0, 100, 100),
+                    ($1, 'b', 'idea', 'B', '', 200, 0, 100, 100)",
+        )
+        .bind(test.project_id)
+        .execute(&test.state.db)
+        .await
+        .unwrap();
+        let request = json!({
+            "title": "frame", "x": 0, "y": 0, "width": 400, "height": 200,
+            "nodeIds": ["a", "b"],
+        });
+        let (_, Json(group)) = create_group(
+            State(test.state.clone()),
+            test.owner(),
+            Path(test.project_id),
+            Json(serde_json::from_value(request).unwrap()),
+        )
+        .await
+        .unwrap();
+
+        let Json(deleted) = delete_group(
+            State(test.state.clone()),
+            test.owner(),
+            LockToken(None),
+            Path((test.project_id, group.id)),
+            Query(DeleteGroupQuery {
+                with_contents: Some(true),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(deleted["trashedNodes"], 2);
+        assert!(fetch_groups(&test.state.db, test.project_id).await.unwrap().is_empty());
+
+        let Json(restored) = restore_node(
+            State(test.state.clone()),
+            test.owner(),
+            Path((test.project_id, "a".to_string())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored.group_id, Some(group.id));
+        let groups = fetch_groups(&test.state.db, test.project_id).await.unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].id, group.id);
+        assert_eq!(groups[0].node_ids, vec!["a".to_string()]);
+
+        test.cleanup().await;
+    }
+}
@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    element_links::Design,
+    error::{AppError, Result},
+    handlers::nodes::{strip_dangling_element_links, verify_project_owner},
+    middleware::auth::AuthUser,
+    models::{
+        canvas_node::ElementLink,
+        integrity::{
+            BrokenElementLink, BrokenLinkReason, BrokenLinksReport, DanglingElementLink,
+            IntegrityReport, RepairQuery,
+        },
+    },
+    state::AppState,
+};
+
+/// Find and remove orphaned connections and element links in a project
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/integrity/repair",
+    params(("project_id" = Uuid, Path, description = "Project UUID"), RepairQuery),
+    responses(
+        (status = 200, description = "Orphans found, removed unless dryRun", body = IntegrityReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn repair_project(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Query(params): Query<RepairQuery>,
+) -> Result<Json<IntegrityReport>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let dry_run = params.dry_run.unwrap_or(false);
+
+    let mut tx = state.db.begin().await?;
+
+    // Foreign keys keep new connections consistent; this catches anything
+    // written before they existed.
+    let orphan_connections = sqlx::query_as::<_, (String, String)>(
+        "SELECT c.from_client_id, c.to_client_id FROM node_connections c
+         WHERE c.project_id = $1
+           AND (NOT EXISTS (
+                    SELECT 1 FROM canvas_nodes n
+                    WHERE n.project_id = c.project_id AND n.client_id = c.from_client_id
+                )
+             OR NOT EXISTS (
+                    SELECT 1 FROM canvas_nodes n
+                    WHERE n.project_id = c.project_id AND n.client_id = c.to_client_id
+                ))",
+    )
+    .bind(project_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let dangling_element_links = sqlx::query_as::<_, DanglingElementLink>(
+        "SELECT n.client_id, el->>'selector' AS selector, el->>'targetNodeId' AS target_node_id
+         FROM canvas_nodes n, jsonb_array_elements(n.element_links) el
+         WHERE n.project_id = $1
+           AND NOT EXISTS (
+               SELECT 1 FROM canvas_nodes t
+               WHERE t.project_id = n.project_id AND t.client_id = el->>'targetNodeId'
+           )
+         ORDER BY n.client_id",
+    )
+    .bind(project_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if !dry_run {
+        if !orphan_connections.is_empty() {
+            let (from, to): (Vec<String>, Vec<String>) =
+                orphan_connections.iter().cloned().unzip();
+            sqlx::query(
+                "DELETE FROM node_connections c
+                 USING UNNEST($2::text[], $3::text[]) AS o(from_id, to_id)
+                 WHERE c.project_id = $1
+                   AND c.from_client_id = o.from_id AND c.to_client_id = o.to_id",
+            )
+            .bind(project_id)
+            .bind(&from)
+            .bind(&to)
+            .execute(&mut *tx)
+            .await?;
+        }
+        if !dangling_element_links.is_empty() {
+            strip_dangling_element_links(&mut tx, &[project_id]).await?;
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(Json(IntegrityReport {
+        dry_run,
+        orphan_connections: orphan_connections
+            .into_iter()
+            .map(|(f, t)| [f, t])
+            .collect(),
+        dangling_element_links,
+    }))
+}
+
+/// Check every element link in a project against its node's HTML and target
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/integrity/element-links",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Links whose selector or target no longer resolves", body = BrokenLinksReport),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn broken_element_links(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<BrokenLinksReport>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let linking = sqlx::query_as::<_, (String, Option<String>, Value)>(
+        "SELECT client_id, content, element_links FROM canvas_nodes
+         WHERE project_id = $1 AND deleted_at IS NULL
+           AND jsonb_typeof(element_links) = 'array' AND jsonb_array_length(element_links) > 0
+         ORDER BY client_id",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+    // client_id -> whether the node is in the trash
+    let trashed: HashMap<String, bool> = sqlx::query_as(
+        "SELECT client_id, deleted_at IS NOT NULL FROM canvas_nodes WHERE project_id = $1",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .collect();
+
+    // Parsing every design is CPU-bound
+    let report = tokio::task::spawn_blocking(move || {
+        let mut checked = 0;
+        let mut broken = Vec::new();
+        for (client_id, content, links) in linking {
+            let links: Vec<ElementLink> = serde_json::from_value(links).unwrap_or_default();
+            let design = Design::parse(content.as_deref());
+            for link in links {
+                checked += 1;
+                let mut reasons = Vec::new();
+                if let Err(problem) = design.resolve(&link.selector) {
+                    reasons.push(BrokenLinkReason::from(problem));
+                }
+                match trashed.get(&link.target_node_id) {
+                    None => reasons.push(BrokenLinkReason::MissingTarget),
+                    Some(true) => reasons.push(BrokenLinkReason::TargetInTrash),
+                    Some(false) => {}
+                }
+                if !reasons.is_empty() {
+                    broken.push(BrokenElementLink {
+                        client_id: client_id.clone(),
+                        selector: link.selector,
+                        label: link.label,
+                        target_node_id: link.target_node_id,
+                        reasons,
+                    });
+                }
+            }
+        }
+        BrokenLinksReport { checked, broken }
+    })
+    .await
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Link check failed: {e}")))?;
+
+    Ok(Json(report))
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::locks,
+    handlers::nodes::{ensure_nodes_exist, verify_project_owner},
+    layout::{self, LayoutNode, Rect, Spacing},
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::layout::{LayoutAlgorithm, LayoutRequest, LayoutResponse, NodePosition},
+    state::AppState,
+};
+
+const DEFAULT_NODE_SPACING: f64 = 40.0;
+const DEFAULT_LAYER_SPACING: f64 = 80.0;
+
+/// Lay out all nodes or a selection with a layered, tree or grid layout. A
+/// laid-out selection is shifted clear of the nodes left out of it.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/layout",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    request_body = LayoutRequest,
+    responses(
+        (status = 200, description = "Proposed or applied positions", body = LayoutResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+        (status = 409, description = "Applying would move a node another session holds the edit lock on"),
+        (status = 422, description = "Unknown node or invalid spacing"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn layout_nodes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path(project_id): Path<Uuid>,
+    Json(req): Json<LayoutRequest>,
+) -> Result<Json<LayoutResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let node_spacing = req.node_spacing.unwrap_or(DEFAULT_NODE_SPACING);
+    let layer_spacing = req.layer_spacing.unwrap_or(DEFAULT_LAYER_SPACING);
+    let valid = |gap: f64| gap.is_finite() && gap >= 0.0;
+    if !valid(node_spacing) || !valid(layer_spacing) {
+        return Err(AppError::Validation(
+            "Spacing must be a non-negative number".into(),
+        ));
+    }
+
+    if let Some(ids) = &req.client_ids {
+        ensure_nodes_exist(&state.db, project_id, ids).await?;
+    }
+
+    let rows = sqlx::query_as::<_, (String, Option<String>, f64, f64, f64, f64)>(
+        "SELECT client_id, parent_id, x, y, width, height FROM canvas_nodes
+         WHERE project_id = $1 AND deleted_at IS NULL
+         ORDER BY created_at, id",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    // Nodes outside the selection stay put and are kept clear of
+    let selected: Option<HashSet<&str>> = req
+        .client_ids
+        .as_ref()
+        .map(|ids| ids.iter().map(String::as_str).collect());
+    let (rows, others): (Vec<_>, Vec<_>) = rows
+        .into_iter()
+        .partition(|r| selected.as_ref().is_none_or(|ids| ids.contains(r.0.as_str())));
+    let obstacles: Vec<Rect> = others
+        .into_iter()
+        .map(|(_, _, x, y, width, height)| Rect {
+            x,
+            y,
+            width,
+            height,
+        })
+        .collect();
+
+    let spacing = Spacing {
+        node: node_spacing,
+        layer: layer_spacing,
+        origin_x: rows.iter().map(|r| r.2).fold(f64::INFINITY, f64::min),
+        origin_y: rows.iter().map(|r| r.3).fold(f64::INFINITY, f64::min),
+    };
+    let nodes: Vec<LayoutNode> = rows
+        .into_iter()
+        .map(|(id, parent_id, _, _, width, height)| LayoutNode {
+            id,
+            parent_id,
+            width,
+            height,
+        })
+        .collect();
+
+    let mut positions = match req.algorithm {
+        LayoutAlgorithm::Layered => {
+            let ids: Vec<&str> = nodes.iter().map(|n| n.id.as_str()).collect();
+            let edges = sqlx::query_as::<_, (String, String)>(
+                "SELECT from_client_id, to_client_id FROM node_connections
+                 WHERE project_id = $1
+                   AND from_client_id = ANY($2) AND to_client_id = ANY($2)",
+            )
+            .bind(project_id)
+            .bind(&ids)
+            .fetch_all(&state.db)
+            .await?;
+            layout::layered(&nodes, &edges, spacing)
+        }
+        LayoutAlgorithm::Tree => layout::tree(&nodes, spacing),
+        LayoutAlgorithm::Grid => layout::grid(&nodes, spacing),
+    };
+    layout::avoid(&mut positions, &nodes, &obstacles, node_spacing);
+
+    // Keep the response in node creation order
+    let positions: Vec<NodePosition> = nodes
+        .iter()
+        .filter_map(|n| {
+            positions.get(&n.id).map(|&(x, y)| NodePosition {
+                client_id: n.id.clone(),
+                x,
+                y,
+            })
+        })
+        .collect();
+
+    let applied = req.apply.unwrap_or(false);
+    if applied && !positions.is_empty() {
+        let ids: Vec<&str> = positions.iter().map(|p| p.client_id.as_str()).collect();
+        let xs: Vec<f64> = positions.iter().map(|p| p.x).collect();
+        let ys: Vec<f64> = positions.iter().map(|p| p.y).collect();
+
+        let mut tx = state.db.begin().await?;
+        let moved: Vec<String> = positions.iter().map(|p| p.client_id.clone()).collect();
+        locks::ensure_none_locked(&mut tx, project_id, &moved, lock_token).await?;
+        sqlx::query(
+            "UPDATE canvas_nodes n SET x = p.x, y = p.y
+             FROM UNNEST($2::text[], $3::float8[], $4::float8[]) AS p(client_id, x, y)
+             WHERE n.project_id = $1 AND n.client_id = p.client_id AND n.deleted_at IS NULL",
+        )
+        .bind(project_id)
+        .bind(&ids)
+        .bind(&xs)
+        .bind(&ys)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+    }
+
+    Ok(Json(LayoutResponse { applied, positions }))
+}
@@ -0,0 +1,284 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use sqlx::PgExecutor;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::locks,
+    handlers::nodes::{
+        ensure_nodes_exist, fetch_node_response, live_node_id, verify_project_owner,
+    },
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::{
+        canvas_node::CanvasNodeResponse,
+        lineage::{LineageNode, LineageQuery, LineageRow, SetParentRequest},
+    },
+    state::AppState,
+};
+
+/// Live node $2 and everything generated from it, for use in a CTE; $3 caps
+/// the depth. The path stops the walk at `parent_id` cycles.
+const DESCENDANTS: &str = "WITH RECURSIVE lineage AS (
+         SELECT client_id, 0 AS depth, ARRAY[client_id] AS path
+         FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL
+         UNION ALL
+         SELECT n.client_id, l.depth + 1, l.path || n.client_id
+         FROM canvas_nodes n JOIN lineage l ON n.parent_id = l.client_id
+         WHERE n.project_id = $1 AND n.deleted_at IS NULL
+           AND n.client_id <> ALL(l.path)
+           AND ($3::int IS NULL OR l.depth < $3)
+     )";
+
+/// Live node $2 and the chain of live parents above it, for use in a CTE
+const ANCESTORS: &str = "WITH RECURSIVE lineage AS (
+         SELECT client_id, parent_id, 0 AS depth, ARRAY[client_id] AS path
+         FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL
+         UNION ALL
+         SELECT p.client_id, p.parent_id, l.depth + 1, l.path || p.client_id
+         FROM canvas_nodes p JOIN lineage l ON p.client_id = l.parent_id
+         WHERE p.project_id = $1 AND p.deleted_at IS NULL
+           AND p.client_id <> ALL(l.path)
+     )";
+
+/// Lineage rows joined with the node fields a tree shows
+const LINEAGE_ROWS: &str =
+    "SELECT n.client_id, n.parent_id, l.depth, n.node_type, n.title, n.status
+     FROM lineage l
+     JOIN canvas_nodes n ON n.project_id = $1 AND n.client_id = l.client_id
+     ORDER BY l.depth, n.created_at, n.id";
+
+/// The chain of nodes a node was generated from, as a tree rooted at the
+/// oldest live ancestor and ending at the node
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/lineage/ancestors",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+    ),
+    responses(
+        (status = 200, description = "Ancestor chain; depth counts generations above the node", body = LineageNode),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn lineage_ancestors(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+) -> Result<Json<LineageNode>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let rows = sqlx::query_as::<_, LineageRow>(&format!("{ANCESTORS} {LINEAGE_ROWS}"))
+        .bind(project_id)
+        .bind(&client_id)
+        .fetch_all(&state.db)
+        .await?;
+    let Some(root) = rows.last().map(|r| r.client_id.clone()) else {
+        return Err(AppError::NotFound(format!("Node '{client_id}' not found")));
+    };
+
+    Ok(Json(build_tree(rows, &root)))
+}
+
+/// Everything generated from a node, directly or through its children
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/lineage/descendants",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        LineageQuery,
+    ),
+    responses(
+        (status = 200, description = "Tree rooted at the node", body = LineageNode),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn lineage_descendants(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(params): Query<LineageQuery>,
+) -> Result<Json<LineageNode>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let rows = sqlx::query_as::<_, LineageRow>(&format!("{DESCENDANTS} {LINEAGE_ROWS}"))
+        .bind(project_id)
+        .bind(&client_id)
+        .bind(params.max_depth)
+        .fetch_all(&state.db)
+        .await?;
+    if rows.is_empty() {
+        return Err(AppError::NotFound(format!("Node '{client_id}' not found")));
+    }
+
+    Ok(Json(build_tree(rows, &client_id)))
+}
+
+/// Move a node, and everything generated from it, under another parent
+#[utoipa::path(
+    put,
+    path = "/api/projects/{project_id}/nodes/{client_id}/parent",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    request_body = SetParentRequest,
+    responses(
+        (status = 200, description = "Updated node", body = CanvasNodeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 409, description = "Another session holds the edit lock"),
+        (status = 422, description = "Parent is not a live node, or would create a cycle"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn set_parent(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Json(req): Json<SetParentRequest>,
+) -> Result<Json<CanvasNodeResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    reparent(&state, lock_token, project_id, &client_id, req.parent_id).await
+}
+
+/// Detach a generated node from its parent so it becomes a root
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/promote",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    responses(
+        (status = 200, description = "Node without a parent", body = CanvasNodeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found"),
+        (status = 409, description = "Another session holds the edit lock"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn promote_node(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+) -> Result<Json<CanvasNodeResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    reparent(&state, lock_token, project_id, &client_id, None).await
+}
+
+/// Move a node and its live descendants to the trash, returning their client_ids
+pub(crate) async fn trash_with_descendants<'e, E>(
+    executor: E,
+    project_id: Uuid,
+    client_id: &str,
+) -> Result<Vec<String>>
+where
+    E: PgExecutor<'e>,
+{
+    Ok(sqlx::query_scalar(&format!(
+        "{DESCENDANTS}
+         UPDATE canvas_nodes SET deleted_at = NOW()
+         WHERE project_id = $1 AND deleted_at IS NULL
+           AND client_id IN (SELECT client_id FROM lineage)
+         RETURNING client_id"
+    ))
+    .bind(project_id)
+    .bind(client_id)
+    .bind(None::<i32>)
+    .fetch_all(executor)
+    .await?)
+}
+
+async fn reparent(
+    state: &AppState,
+    lock_token: Option<Uuid>,
+    project_id: Uuid,
+    client_id: &str,
+    parent_id: Option<String>,
+) -> Result<Json<CanvasNodeResponse>> {
+    let mut tx = state.db.begin().await?;
+    let node_id = live_node_id(&mut *tx, project_id, client_id).await?;
+    locks::ensure_unlocked(&mut tx, node_id, client_id, lock_token).await?;
+
+    if let Some(parent_id) = &parent_id {
+        ensure_nodes_exist(&mut *tx, project_id, std::slice::from_ref(parent_id)).await?;
+        let cycle: bool = sqlx::query_scalar(&format!(
+            "{DESCENDANTS} SELECT EXISTS (SELECT 1 FROM lineage WHERE client_id = $4)"
+        ))
+        .bind(project_id)
+        .bind(client_id)
+        .bind(None::<i32>)
+        .bind(parent_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if cycle {
+            return Err(AppError::Validation(
+                "parentId: a node cannot be generated from itself or its descendants".into(),
+            ));
+        }
+    }
+
+    sqlx::query("UPDATE canvas_nodes SET parent_id = $2 WHERE id = $1")
+        .bind(node_id)
+        .bind(&parent_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    let node = fetch_node_response(state, project_id, client_id).await?;
+    Ok(Json(node))
+}
+
+/// Nest walk rows under `root` by parent; each row is placed at most once,
+/// so a `parent_id` cycle cannot recurse forever
+fn build_tree(rows: Vec<LineageRow>, root: &str) -> LineageNode {
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for row in &rows {
+        if let Some(parent_id) = &row.parent_id {
+            children
+                .entry(parent_id.clone())
+                .or_default()
+                .push(row.client_id.clone());
+        }
+    }
+    let mut rows: HashMap<String, LineageRow> =
+        rows.into_iter().map(|r| (r.client_id.clone(), r)).collect();
+
+    fn place(
+        id: &str,
+        rows: &mut HashMap<String, LineageRow>,
+        children: &mut HashMap<String, Vec<String>>,
+    ) -> Option<LineageNode> {
+        let row = rows.remove(id)?;
+        let kids = children.remove(id).unwrap_or_default();
+        Some(LineageNode {
+            children: kids
+                .iter()
+                .filter_map(|kid| place(kid, rows, children))
+                .collect(),
+            client_id: row.client_id,
+            node_type: row.node_type,
+            title: row.title,
+            status: row.status,
+            depth: row.depth,
+        })
+    }
+
+    place(root, &mut rows, &mut children).expect("root is among the walked rows")
+}
@@ -0,0 +1,367 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::{json, Value};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::nodes::{live_node_id, verify_project_owner},
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::node_lock::{HeldLock, LockRequest, NodeLock, ReleaseLockQuery},
+    state::AppState,
+};
+
+/// Active locks joined with their node and holder, for use with a WHERE suffix
+const ACTIVE_LOCKS: &str = "SELECT n.client_id, l.holder_id,
+            COALESCE(NULLIF(u.display_name, ''), u.email) AS holder_name,
+            l.acquired_at, l.expires_at, l.token
+     FROM node_locks l
+     JOIN canvas_nodes n ON n.id = l.node_id
+     JOIN users u ON u.id = l.holder_id
+     WHERE l.expires_at > NOW()";
+
+/// List the active edit locks of a project
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/locks",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Unexpired locks on live nodes", body = Vec<NodeLock>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_locks(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<NodeLock>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let locks = sqlx::query_as::<_, NodeLock>(&format!(
+        "{ACTIVE_LOCKS} AND l.project_id = $1 AND n.deleted_at IS NULL ORDER BY n.client_id"
+    ))
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(locks))
+}
+
+/// Take the edit lock on a node, or extend it if the calling session already
+/// holds it. A session's first lock mints its token; sending it back in
+/// `X-Lock-Token` puts further locks under the same token.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/lock",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Token of the calling session"),
+    ),
+    request_body = LockRequest,
+    responses(
+        (status = 200, description = "Lock held by the calling session", body = HeldLock),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+        (status = 409, description = "Another session holds the lock"),
+        (status = 422, description = "TTL out of range, or malformed lock token"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn acquire_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Json(req): Json<LockRequest>,
+) -> Result<Json<HeldLock>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let ttl = lock_ttl(&state, req.ttl_seconds)?;
+    let node_id = live_node_id(&state.db, project_id, &client_id).await?;
+    let token = token.unwrap_or_else(Uuid::new_v4);
+    let mut conn = state.db.acquire().await?;
+
+    // An expired lock is free for anyone; re-acquiring keeps the original start
+    let rows = sqlx::query(
+        "INSERT INTO node_locks (node_id, project_id, holder_id, expires_at, token)
+         VALUES ($1, $2, $3, NOW() + make_interval(secs => $4), $5)
+         ON CONFLICT (node_id) DO UPDATE SET
+             holder_id   = EXCLUDED.holder_id,
+             expires_at  = EXCLUDED.expires_at,
+             token       = EXCLUDED.token,
+             acquired_at = CASE WHEN node_locks.expires_at > NOW()
+                                THEN node_locks.acquired_at ELSE NOW() END
+         WHERE node_locks.token = EXCLUDED.token OR node_locks.expires_at <= NOW()",
+    )
+    .bind(node_id)
+    .bind(project_id)
+    .bind(auth.user_id)
+    .bind(ttl as f64)
+    .bind(token)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    if rows == 0 {
+        ensure_unlocked(&mut conn, node_id, &client_id, Some(token)).await?;
+    }
+
+    let lock = fetch_lock(&mut conn, node_id).await?.ok_or_else(|| {
+        AppError::Conflict(format!("Lock on node '{client_id}' expired immediately"))
+    })?;
+    Ok(Json(HeldLock {
+        lock,
+        lock_token: token,
+    }))
+}
+
+/// Extend a lock the calling session holds
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/lock/renew",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Uuid, Header, description = "Token the lock was taken with"),
+    ),
+    request_body = LockRequest,
+    responses(
+        (status = 200, description = "Renewed lock", body = HeldLock),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+        (status = 409, description = "The calling session no longer holds the lock"),
+        (status = 422, description = "TTL out of range, or malformed lock token"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn renew_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Json(req): Json<LockRequest>,
+) -> Result<Json<HeldLock>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let ttl = lock_ttl(&state, req.ttl_seconds)?;
+    let node_id = live_node_id(&state.db, project_id, &client_id).await?;
+    let mut conn = state.db.acquire().await?;
+
+    let rows = sqlx::query(
+        "UPDATE node_locks SET expires_at = NOW() + make_interval(secs => $3)
+         WHERE node_id = $1 AND token = $2 AND expires_at > NOW()",
+    )
+    .bind(node_id)
+    .bind(token)
+    .bind(ttl as f64)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    let lost = || AppError::Conflict(format!("You no longer hold the lock on node '{client_id}'"));
+    if rows == 0 {
+        return Err(lost());
+    }
+
+    let lock = fetch_lock(&mut conn, node_id).await?.ok_or_else(lost)?;
+    Ok(Json(HeldLock {
+        lock_token: lock.token,
+        lock,
+    }))
+}
+
+/// Release a node's lock. Project owners can break a lock held by another
+/// session, their own included, with `force=true`.
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/nodes/{client_id}/lock",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Token the lock was taken with"),
+        ReleaseLockQuery,
+    ),
+    responses(
+        (status = 200, description = "Node is unlocked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Another session holds the lock and force was not set"),
+        (status = 404, description = "Project or node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn release_lock(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(token): LockToken,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(query): Query<ReleaseLockQuery>,
+) -> Result<Json<Value>> {
+    // Only the project owner gets past this check, so `force` needs no
+    // further permission test
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let node_id = live_node_id(&state.db, project_id, &client_id).await?;
+    let mut conn = state.db.acquire().await?;
+
+    let broken = match fetch_lock(&mut conn, node_id).await? {
+        Some(lock) if Some(lock.token) != token => {
+            if !query.force.unwrap_or(false) {
+                return Err(AppError::Forbidden);
+            }
+            true
+        }
+        _ => false,
+    };
+    sqlx::query("DELETE FROM node_locks WHERE node_id = $1")
+        .bind(node_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(Json(
+        json!({ "message": "Lock released", "broken": broken }),
+    ))
+}
+
+/// Reject a write to a node while a session other than the one holding
+/// `token` has it locked
+pub(crate) async fn ensure_unlocked(
+    conn: &mut PgConnection,
+    node_id: Uuid,
+    client_id: &str,
+    token: Option<Uuid>,
+) -> Result<()> {
+    match fetch_lock(conn, node_id).await? {
+        Some(lock) if Some(lock.token) != token => Err(AppError::Conflict(format!(
+            "Node '{client_id}' is locked by {} in another session until {}",
+            lock.holder_name,
+            lock.expires_at.to_rfc3339()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Reject a change to several nodes when another session holds a lock on any
+/// of them. Bulk paths call it inside their transaction with the client_ids the
+/// change touched, so nodes it selected itself (descendants, group members)
+/// are covered too.
+pub(crate) async fn ensure_none_locked(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    client_ids: &[String],
+    token: Option<Uuid>,
+) -> Result<()> {
+    if client_ids.is_empty() {
+        return Ok(());
+    }
+    let locks = sqlx::query_as::<_, NodeLock>(&format!(
+        "{ACTIVE_LOCKS} AND l.project_id = $1 AND n.client_id = ANY($2)
+           AND l.token IS DISTINCT FROM $3
+         ORDER BY n.client_id"
+    ))
+    .bind(project_id)
+    .bind(client_ids)
+    .bind(token)
+    .fetch_all(&mut *conn)
+    .await?;
+    if locks.is_empty() {
+        return Ok(());
+    }
+    let held: Vec<String> = locks
+        .iter()
+        .map(|l| format!("'{}' by {}", l.client_id, l.holder_name))
+        .collect();
+    Err(AppError::Conflict(format!(
+        "Locked in another session: {}",
+        held.join(", ")
+    )))
+}
+
+fn lock_ttl(state: &AppState, requested: Option<i64>) -> Result<i64> {
+    let max = state.cfg.node_lock_max_ttl_secs;
+    match requested {
+        None => Ok(state.cfg.node_lock_default_ttl_secs.min(max)),
+        Some(ttl) if (1..=max).contains(&ttl) => Ok(ttl),
+        Some(_) => Err(AppError::Validation(format!(
+            "ttlSeconds must be between 1 and {max}"
+        ))),
+    }
+}
+
+async fn fetch_lock(conn: &mut PgConnection, node_id: Uuid) -> Result<Option<NodeLock>> {
+    let lock = sqlx::query_as::<_, NodeLock>(&format!("{ACTIVE_LOCKS} AND l.node_id = $1"))
+        .bind(node_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(lock)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::nodes::update_node;
+    use crate::test_support::TestProject;
+
+    /// Two tabs of the same owner: only the one that took the lock may write
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn lock_blocks_other_sessions_of_the_same_owner() {
+        let test = TestProject::new().await;
+        sqlx::query(
+            "INSERT INTO canvas_nodes
+             (project_id, client_id, node_type, title, description, x, y, width, height)
+             VALUES ($1, 'a', 'idea', 'A', '', 0, 0, 100, 100)",
+        )
+        .bind(test.project_id)
+        .execute(&test.state.db)
+        .await
+        .unwrap();
+        let path = || Path((test.project_id, "a".to_string()));
+        let rename = |title: &str| Json(serde_json::from_value(json!({ "title": title })).unwrap());
+
+        let Json(held) = acquire_lock(
+            State(test.state.clone()),
+            test.owner(),
+            LockToken(None),
+            path(),
+            Json(LockRequest { ttl_seconds: None }),
+        )
+        .await
+        .unwrap();
+
+        for other in [None, Some(Uuid::new_v4())] {
+            let write = update_node(
+                State(test.state.clone()),
+                test.owner(),
+                LockToken(other),
+                path(),
+                rename("other tab"),
+            )
+            .await;
+            assert!(matches!(write, Err(AppError::Conflict(_))));
+
+            let lock = acquire_lock(
+                State(test.state.clone()),
+                test.owner(),
+                LockToken(other),
+                path(),
+                Json(LockRequest { ttl_seconds: None }),
+            )
+            .await;
+            assert!(matches!(lock, Err(AppError::Conflict(_))));
+        }
+
+        let Json(node) = update_node(
+            State(test.state.clone()),
+            test.owner(),
+            LockToken(Some(held.lock_token)),
+            path(),
+            rename("holding tab"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(node.title, "holding tab");
+
+        test.cleanup().await;
+    }
+}
@@ -1,5 +1,19 @@
 pub mod ai_proxy;
+pub mod assets;
 pub mod auth;
+pub mod comments;
+pub mod environments;
+pub mod graph;
+pub mod groups;
+pub mod integrity;
+pub mod layout;
+pub mod lineage;
+pub mod locks;
+pub mod node_status;
 pub mod nodes;
 pub mod projects;
+pub mod revisions;
+pub mod search;
+pub mod secrets;
+pub mod trash;
 pub mod variations;
@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::nodes::{live_node_id, verify_project_owner},
+    middleware::auth::AuthUser,
+    models::canvas_node::{NodeStatus, NodeStatusChange},
+    state::AppState,
+};
+
+/// List a node's status changes, oldest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/status-history",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+    ),
+    responses(
+        (status = 200, description = "Status changes, starting with the status the node was created with", body = Vec<NodeStatusChange>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_status_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+) -> Result<Json<Vec<NodeStatusChange>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let node_id = live_node_id(&state.db, project_id, &client_id).await?;
+
+    let changes = sqlx::query_as::<_, NodeStatusChange>(
+        "SELECT from_status, to_status, changed_at FROM node_status_changes
+         WHERE node_id = $1
+         ORDER BY changed_at, id",
+    )
+    .bind(node_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(changes))
+}
+
+/// Reject a status change the lifecycle does not allow
+pub(crate) fn ensure_transition(client_id: &str, from: &NodeStatus, to: &NodeStatus) -> Result<()> {
+    if from.can_become(to) {
+        return Ok(());
+    }
+    Err(AppError::Conflict(format!(
+        "Node '{client_id}' cannot go from {} to {}",
+        from.as_str(),
+        to.as_str()
+    )))
+}
@@ -1,31 +1,43 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     Json,
 };
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use sqlx::{PgConnection, Postgres, QueryBuilder};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::{
+    element_links::Design,
     error::{AppError, Result},
-    middleware::auth::AuthUser,
+    handlers::{assets, groups, lineage, locks, node_status, revisions},
+    middleware::{auth::AuthUser, lock_token::LockToken},
     models::canvas_node::{
-        CanvasNode, CanvasNodeResponse, ConnectNodesRequest, CreateNodeRequest,
-        DisconnectNodesRequest, ElementLink, NodeStatus, UpdateNodeRequest,
+        CanvasConnectionInput, CanvasNode, CanvasNodeResponse, ConnectNodesRequest, Connection,
+        ConnectionKind, CreateNodeRequest, DeleteNodeQuery, DisconnectNodesRequest,
+        DuplicateNodesRequest, DuplicateNodesResponse, ElementLink, ListNodesQuery,
+        NodeContentRequest, NodeContentResponse, NodePlatform, NodeSort, NodeStatus, NodeType,
+        UpdateConnectionRequest, UpdateNodeRequest,
     },
+    models::integrity::BrokenLinkReason,
+    models::node_payload::NodePayload,
+    models::node_revision::RevisionFields,
+    pagination::{list_limit, push_page, Cursor, Page, SortOrder, MAX_LIMIT},
+    secrets,
     state::AppState,
 };
 
-/// List all nodes for a project
+/// List nodes for a project with filtering, sorting and cursor pagination
 #[utoipa::path(
     get,
     path = "/api/projects/{project_id}/nodes",
-    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    params(("project_id" = Uuid, Path, description = "Project UUID"), ListNodesQuery),
     responses(
-        (status = 200, description = "List of canvas nodes", body = Vec<CanvasNodeResponse>),
+        (status = 200, description = "Page of canvas nodes; total in X-Total-Count, next page in X-Next-Cursor", body = Vec<CanvasNodeResponse>),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Project not found"),
+        (status = 422, description = "Invalid cursor, bbox or unknown field"),
     ),
     security(("bearer_auth" = []))
 )]
@@ -33,34 +45,129 @@ pub async fn list_nodes(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(project_id): Path<Uuid>,
-) -> Result<Json<Vec<CanvasNodeResponse>>> {
+    Query(params): Query<ListNodesQuery>,
+) -> Result<Page<Value>> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let nodes = sqlx::query_as::<_, CanvasNode>(
-        "SELECT * FROM canvas_nodes WHERE project_id = $1 ORDER BY created_at ASC",
-    )
-    .bind(project_id)
-    .fetch_all(&state.db)
-    .await?;
+    let fieldset =
+        NodeFieldSet::parse(params.fields.as_deref(), params.summary.unwrap_or(false))?;
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or(SortOrder::Asc);
+    let cursor = params.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let limit = list_limit(params.limit, cursor.as_ref());
+    let bbox = params.bbox.as_deref().map(BoundingBox::parse).transpose()?;
 
-    let conn_rows = sqlx::query_as::<_, (String, String)>(
-        "SELECT from_client_id, to_client_id FROM node_connections WHERE project_id = $1",
-    )
-    .bind(project_id)
-    .fetch_all(&state.db)
-    .await?;
+    let push_filters = |qb: &mut QueryBuilder<'_, Postgres>| {
+        qb.push(" WHERE project_id = ")
+            .push_bind(project_id)
+            .push(" AND deleted_at IS NULL");
+        if let Some(node_type) = &params.node_type {
+            qb.push(" AND node_type = ").push_bind(node_type.clone());
+        }
+        if let Some(status) = &params.status {
+            qb.push(" AND status = ").push_bind(status.clone());
+        }
+        if let Some(tag) = &params.tag {
+            qb.push(" AND tag = ").push_bind(tag.clone());
+        }
+        if let Some(platform) = &params.platform {
+            qb.push(" AND platform = ").push_bind(platform.clone());
+        }
+        if let Some(picked) = params.picked {
+            qb.push(" AND picked = ").push_bind(picked);
+        }
+        if let Some(parent_id) = &params.parent_id {
+            qb.push(" AND parent_id = ").push_bind(parent_id.clone());
+        }
+        if let Some(bbox) = &bbox {
+            bbox.push_filter(qb);
+        }
+    };
+
+    let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM canvas_nodes");
+    push_filters(&mut count_qb);
+    let total: i64 = count_qb.build_query_scalar().fetch_one(&state.db).await?;
+
+    let mut qb = QueryBuilder::new(format!("SELECT {} FROM canvas_nodes", fieldset.columns()));
+    push_filters(&mut qb);
+    push_page(&mut qb, sort.column(), "id", order, cursor, limit)?;
+    let nodes: Vec<CanvasNode> = qb.build_query_as().fetch_all(&state.db).await?;
+
+    let conn_rows = fetch_live_connections(&state, project_id, None).await?;
+    let trashed = fetch_trashed_client_ids(&state.db, project_id).await?;
 
     let mut connected_to_map: HashMap<String, Vec<String>> = HashMap::new();
     for (from, to) in conn_rows {
         connected_to_map.entry(from).or_default().push(to);
     }
 
-    let responses = nodes
-        .into_iter()
-        .map(|n| node_to_response(n, &connected_to_map))
-        .collect();
+    let page = Page::from_rows(nodes, limit, total, |n| match sort {
+        NodeSort::CreatedAt => Cursor::timestamp(n.created_at, n.id),
+        NodeSort::UpdatedAt => Cursor::timestamp(n.updated_at, n.id),
+        NodeSort::Title => Cursor::text(&n.title, n.id),
+    });
 
-    Ok(Json(responses))
+    Ok(page.map(|n| fieldset.apply(node_to_response(n, &connected_to_map, &trashed))))
+}
+
+/// Fetch heavy node bodies for a set of nodes
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/node-content",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    request_body = NodeContentRequest,
+    responses(
+        (status = 200, description = "Bodies of the requested nodes", body = Vec<NodeContentResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+        (status = 422, description = "Too many client_ids"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn fetch_node_content(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Json(req): Json<NodeContentRequest>,
+) -> Result<Json<Vec<NodeContentResponse>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    if req.client_ids.len() as i64 > MAX_LIMIT {
+        return Err(AppError::Validation(format!(
+            "At most {MAX_LIMIT} nodes can be fetched at once"
+        )));
+    }
+
+    let (known_ids, known_hashes): (Vec<String>, Vec<String>) =
+        req.known_hashes.unwrap_or_default().into_iter().unzip();
+
+    let bodies = sqlx::query_as::<_, NodeContentResponse>(
+        "SELECT n.client_id, n.node_type, n.content_hash,
+                COALESCE(n.content_hash = k.hash, FALSE) AS unchanged,
+                CASE WHEN n.content_hash = k.hash THEN NULL ELSE n.content END AS content,
+                CASE WHEN n.content_hash = k.hash THEN NULL ELSE n.generated_code END
+                    AS generated_code
+         FROM canvas_nodes n
+         LEFT JOIN UNNEST($3::text[], $4::text[]) AS k(client_id, hash)
+                ON k.client_id = n.client_id
+         WHERE n.project_id = $1 AND n.client_id = ANY($2) AND n.deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(&req.client_ids)
+    .bind(&known_ids)
+    .bind(&known_hashes)
+    .fetch_all(&state.db)
+    .await?
+    .into_iter()
+    .map(|mut body| {
+        if matches!(body.node_type, NodeType::Payment) {
+            body.generated_code = body.generated_code.map(secrets::mask_payment_code);
+        }
+        body
+    })
+    .collect::<Vec<_>>();
+
+    Ok(Json(bodies))
 }
 
 /// Create a new node in a project
@@ -74,6 +181,7 @@ pub async fn list_nodes(
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Project not found"),
         (status = 409, description = "Node with this client_id already exists"),
+        (status = 422, description = "Malformed payload, or unknown node or asset"),
     ),
     security(("bearer_auth" = []))
 )]
@@ -85,18 +193,43 @@ pub async fn create_node(
 ) -> Result<(StatusCode, Json<CanvasNodeResponse>)> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let element_links =
-        serde_json::to_value(req.element_links.as_deref().unwrap_or(&[])).unwrap_or_default();
+    let secret = &state.cfg.api_key_encryption_secret;
+    let mut payload = NodePayload::validate(&req.node_type, req.payload.as_ref())?;
+    let generated_code = seal_payment_secrets(
+        &req.node_type,
+        &mut payload,
+        req.generated_code.as_deref(),
+        &StoredSecrets::default(),
+        secret,
+    )?;
+    let connected_to = req.connected_to.clone().unwrap_or_default();
+    ensure_nodes_exist(&state.db, project_id, &connected_to).await?;
+    let asset_ids = req.asset_ids.as_deref().unwrap_or_default();
+    assets::ensure_assets_exist(&state.db, project_id, asset_ids).await?;
+
+    let element_links = req.element_links.clone().unwrap_or_default();
+    let mut element_links = [resolve_element_links(req.content.clone(), element_links).await?];
+    flag_missing_link_targets(&state.db, project_id, &[&req.client_id], &mut element_links)
+        .await?;
+    let element_links = serde_json::to_value(&element_links[0]).unwrap_or_default();
     let empty_map = HashMap::<String, String>::new();
-    let env_vars =
-        serde_json::to_value(req.env_vars.as_ref().unwrap_or(&empty_map)).unwrap_or_default();
+    let env_vars = secrets::seal_env_vars(
+        req.env_vars.as_ref().unwrap_or(&empty_map),
+        req.secret_env_vars.as_deref(),
+        &empty_map,
+        secret,
+    )?;
+    let env_vars = serde_json::to_value(env_vars).unwrap_or_default();
+
+    let mut tx = state.db.begin().await?;
 
     let node = sqlx::query_as::<_, CanvasNode>(
         "INSERT INTO canvas_nodes
          (project_id, client_id, node_type, title, description, x, y, width, height,
           status, content, file_name, generated_code, picked, parent_id, page_role,
-          tag, platform, language, ai_model, element_links, env_vars)
-         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22)
+          tag, platform, language, ai_model, element_links, env_vars, payload, asset_ids)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19,$20,$21,$22,
+                 $23,$24)
          RETURNING *",
     )
     .bind(project_id)
@@ -111,7 +244,7 @@ pub async fn create_node(
     .bind(req.status.as_ref().unwrap_or(&NodeStatus::Idle))
     .bind(&req.content)
     .bind(&req.file_name)
-    .bind(&req.generated_code)
+    .bind(&generated_code)
     .bind(req.picked.unwrap_or(false))
     .bind(&req.parent_id)
     .bind(&req.page_role)
@@ -121,7 +254,9 @@ pub async fn create_node(
     .bind(&req.ai_model)
     .bind(element_links)
     .bind(env_vars)
-    .fetch_one(&state.db)
+    .bind(payload)
+    .bind(serde_json::to_value(asset_ids).unwrap_or_default())
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| {
         if let sqlx::Error::Database(ref db_err) = e {
@@ -135,25 +270,22 @@ pub async fn create_node(
         AppError::Database(e)
     })?;
 
-    let connected_to = req.connected_to.clone().unwrap_or_default();
-
-    if let Some(ref targets) = req.connected_to {
-        for target_id in targets {
-            sqlx::query(
-                "INSERT INTO node_connections (project_id, from_client_id, to_client_id)
-                 VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
-            )
-            .bind(project_id)
-            .bind(&req.client_id)
-            .bind(target_id)
-            .execute(&state.db)
-            .await?;
-        }
+    if !connected_to.is_empty() {
+        let connections: Vec<ConnectNodesRequest> = connected_to
+            .iter()
+            .map(|to| {
+                CanvasConnectionInput::Pair([req.client_id.clone(), to.clone()]).into_request()
+            })
+            .collect();
+        upsert_connections(&mut tx, project_id, &connections).await?;
     }
 
+    tx.commit().await?;
+
+    let trashed = fetch_trashed_client_ids(&state.db, project_id).await?;
     let mut map = HashMap::new();
     map.insert(node.client_id.clone(), connected_to);
-    Ok((StatusCode::CREATED, Json(node_to_response(node, &map))))
+    Ok((StatusCode::CREATED, Json(node_to_response(node, &map, &trashed))))
 }
 
 /// Get a single node by client_id
@@ -188,33 +320,101 @@ pub async fn get_node(
     params(
         ("project_id" = Uuid, Path, description = "Project UUID"),
         ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
     ),
     request_body = UpdateNodeRequest,
     responses(
         (status = 200, description = "Updated node", body = CanvasNodeResponse),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Node not found"),
+        (status = 409, description = "Illegal status transition, or another session holds the edit lock"),
+        (status = 422, description = "Invalid payload, or unknown asset"),
     ),
     security(("bearer_auth" = []))
 )]
 pub async fn update_node(
     State(state): State<AppState>,
     auth: AuthUser,
+    LockToken(lock_token): LockToken,
     Path((project_id, client_id)): Path<(Uuid, String)>,
     Json(req): Json<UpdateNodeRequest>,
 ) -> Result<Json<CanvasNodeResponse>> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let element_links_val = req
-        .element_links
-        .as_ref()
-        .map(|v| serde_json::to_value(v).unwrap_or_default());
-    let env_vars_val = req
-        .env_vars
-        .as_ref()
-        .map(|v| serde_json::to_value(v).unwrap_or_default());
+    let asset_ids_val = match &req.asset_ids {
+        Some(ids) => {
+            assets::ensure_assets_exist(&state.db, project_id, ids).await?;
+            Some(serde_json::to_value(ids).unwrap_or_default())
+        }
+        None => None,
+    };
 
-    sqlx::query(
+    let mut tx = state.db.begin().await?;
+    let locked = revisions::lock_node(&mut tx, project_id, &client_id).await?;
+    locks::ensure_unlocked(&mut tx, locked.id, &client_id, lock_token).await?;
+    if let Some(status) = &req.status {
+        node_status::ensure_transition(&client_id, &locked.status, status)?;
+    }
+
+    let mut payload_val = match &req.payload {
+        Some(raw) if !raw.is_null() => NodePayload::validate(&locked.node_type, Some(raw))?,
+        _ => None,
+    };
+
+    // Masked secrets stand for the values stored now
+    let secret = &state.cfg.api_key_encryption_secret;
+    let stored = sqlx::query_as::<_, StoredSecrets>(
+        "SELECT env_vars, payload, generated_code FROM canvas_nodes WHERE id = $1",
+    )
+    .bind(locked.id)
+    .fetch_one(&mut *tx)
+    .await?;
+    let generated_code = seal_payment_secrets(
+        &locked.node_type,
+        &mut payload_val,
+        req.generated_code.as_deref(),
+        &stored,
+        secret,
+    )?;
+    let env_vars_val = match (&req.env_vars, &req.secret_env_vars) {
+        (None, None) => None,
+        (incoming, marked) => {
+            let stored_vars = stored.env_vars();
+            // Re-marking alone keeps every value
+            let incoming = incoming.clone().unwrap_or_else(|| {
+                stored_vars.keys().map(|k| (k.clone(), secrets::MASK.to_string())).collect()
+            });
+            let sealed =
+                secrets::seal_env_vars(&incoming, marked.as_deref(), &stored_vars, secret)?;
+            Some(serde_json::to_value(sealed).unwrap_or_default())
+        }
+    };
+    // Links are checked against the HTML the node will have after this update,
+    // so new HTML re-checks the stored links too
+    let links = match (req.element_links.clone(), &req.content) {
+        (Some(links), _) => Some(links),
+        (None, Some(_)) => Some(
+            serde_json::from_value(
+                sqlx::query_scalar("SELECT element_links FROM canvas_nodes WHERE id = $1")
+                    .bind(locked.id)
+                    .fetch_one(&mut *tx)
+                    .await?,
+            )
+            .unwrap_or_default(),
+        ),
+        (None, None) => None,
+    };
+    let element_links_val = match links {
+        Some(links) => {
+            let content = req.content.clone().or_else(|| locked.fields.content.clone());
+            let mut links = [resolve_element_links(content, links).await?];
+            flag_missing_link_targets(&mut *tx, project_id, &[], &mut links).await?;
+            Some(serde_json::to_value(&links[0]).unwrap_or_default())
+        }
+        None => None,
+    };
+
+    sqlx::query(&format!(
         "UPDATE canvas_nodes SET
             title          = COALESCE($3, title),
             description    = COALESCE($4, description),
@@ -232,10 +432,13 @@ pub async fn update_node(
             platform       = COALESCE($16, platform),
             language       = COALESCE($17, language),
             ai_model       = COALESCE($18, ai_model),
-            element_links  = COALESCE($19, element_links),
-            env_vars       = COALESCE($20, env_vars)
-         WHERE project_id = $1 AND client_id = $2",
-    )
+            element_links  = COALESCE({}, element_links),
+            env_vars       = COALESCE($20, env_vars),
+            payload        = COALESCE($21, payload),
+            asset_ids      = COALESCE($22, asset_ids)
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+        with_trashed_links("$19")
+    ))
     .bind(project_id)
     .bind(&client_id)
     .bind(req.title.as_deref())
@@ -247,7 +450,7 @@ pub async fn update_node(
     .bind(req.status.as_ref())
     .bind(req.content.as_deref())
     .bind(req.file_name.as_deref())
-    .bind(req.generated_code.as_deref())
+    .bind(generated_code.as_deref())
     .bind(req.picked)
     .bind(req.page_role.as_deref())
     .bind(req.tag.as_deref())
@@ -256,49 +459,75 @@ pub async fn update_node(
     .bind(req.ai_model.as_deref())
     .bind(element_links_val)
     .bind(env_vars_val)
-    .execute(&state.db)
+    .bind(payload_val)
+    .bind(asset_ids_val)
+    .execute(&mut *tx)
     .await?;
 
+    let before = &locked.fields;
+    let after = RevisionFields {
+        title: req.title.unwrap_or_else(|| before.title.clone()),
+        description: req.description.unwrap_or_else(|| before.description.clone()),
+        content: req.content.or_else(|| before.content.clone()),
+        generated_code: generated_code.or_else(|| before.generated_code.clone()),
+    };
+    revisions::record_revision(&mut tx, &locked, &after, auth.user_id).await?;
+    tx.commit().await?;
+
     let node = fetch_node_response(&state, project_id, &client_id).await?;
     Ok(Json(node))
 }
 
-/// Delete a node (and its connections)
+/// Move a node to the project trash; its connections stay hidden until it is
+/// restored, and are deleted along with it when the trash is purged
 #[utoipa::path(
     delete,
     path = "/api/projects/{project_id}/nodes/{client_id}",
     params(
         ("project_id" = Uuid, Path, description = "Project UUID"),
         ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        DeleteNodeQuery,
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
     ),
     responses(
-        (status = 200, description = "Deleted successfully"),
+        (status = 200, description = "Node moved to trash; `trashed` lists every node moved"),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Node not found"),
+        (status = 409, description = "Another session holds the edit lock on a node to be trashed"),
     ),
     security(("bearer_auth" = []))
 )]
 pub async fn delete_node(
     State(state): State<AppState>,
     auth: AuthUser,
+    LockToken(lock_token): LockToken,
     Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(params): Query<DeleteNodeQuery>,
 ) -> Result<Json<Value>> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let rows = sqlx::query(
-        "DELETE FROM canvas_nodes WHERE project_id = $1 AND client_id = $2",
-    )
-    .bind(project_id)
-    .bind(&client_id)
-    .execute(&state.db)
-    .await?
-    .rows_affected();
+    let mut tx = state.db.begin().await?;
+    let trashed: Vec<String> = if params.with_descendants.unwrap_or(false) {
+        lineage::trash_with_descendants(&mut *tx, project_id, &client_id).await?
+    } else {
+        sqlx::query_scalar(
+            "UPDATE canvas_nodes SET deleted_at = NOW()
+             WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL
+             RETURNING client_id",
+        )
+        .bind(project_id)
+        .bind(&client_id)
+        .fetch_all(&mut *tx)
+        .await?
+    };
 
-    if rows == 0 {
+    if trashed.is_empty() {
         return Err(AppError::NotFound(format!("Node '{client_id}' not found")));
     }
+    locks::ensure_none_locked(&mut tx, project_id, &trashed, lock_token).await?;
+    tx.commit().await?;
 
-    Ok(Json(json!({ "message": "Node deleted" })))
+    Ok(Json(json!({ "message": "Node moved to trash", "trashed": trashed })))
 }
 
 /// Duplicate a node (copies data with new client_id, offset position)
@@ -323,23 +552,21 @@ pub async fn duplicate_node(
 ) -> Result<(StatusCode, Json<CanvasNodeResponse>)> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let new_client_id = format!(
-        "{}-copy-{}",
-        client_id,
-        chrono::Utc::now().timestamp_millis()
-    );
+    let new_client_id = format!("{client_id}-copy-{}", Uuid::new_v4().simple());
 
     let node = sqlx::query_as::<_, CanvasNode>(
         "INSERT INTO canvas_nodes
          (project_id, client_id, node_type, title, description, x, y, width, height,
           status, content, file_name, generated_code, picked, parent_id, page_role,
-          tag, platform, language, ai_model, element_links, env_vars)
+          tag, platform, language, ai_model, element_links, env_vars, payload, group_id,
+          asset_ids)
          SELECT project_id, $3, node_type, title || ' (copy)', description,
                 x + 20, y + 20, width, height,
                 status, content, file_name, generated_code, picked, parent_id, page_role,
-                tag, platform, language, ai_model, element_links, env_vars
+                tag, platform, language, ai_model, element_links, env_vars, payload, group_id,
+                asset_ids
          FROM canvas_nodes
-         WHERE project_id = $1 AND client_id = $2
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL
          RETURNING *",
     )
     .bind(project_id)
@@ -349,8 +576,178 @@ pub async fn duplicate_node(
     .await?
     .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))?;
 
+    let trashed = fetch_trashed_client_ids(&state.db, project_id).await?;
     let empty: HashMap<String, Vec<String>> = HashMap::new();
-    Ok((StatusCode::CREATED, Json(node_to_response(node, &empty))))
+    Ok((StatusCode::CREATED, Json(node_to_response(node, &empty, &trashed))))
+}
+
+/// Duplicate a selection of nodes as a subgraph, optionally into another project.
+/// Connections among the selected nodes are recreated, and `parent_id`, element
+/// link targets and env node bases inside the selection point at the copies.
+/// References leaving the selection are kept within the same project and
+/// dropped when copying elsewhere, as are group memberships.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/duplicate-nodes",
+    params(("project_id" = Uuid, Path, description = "Source project UUID")),
+    request_body = DuplicateNodesRequest,
+    responses(
+        (status = 201, description = "Copies created", body = DuplicateNodesResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Source or target project not found"),
+        (status = 422, description = "Empty selection or unknown node"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn duplicate_nodes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Json(req): Json<DuplicateNodesRequest>,
+) -> Result<(StatusCode, Json<DuplicateNodesResponse>)> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let target_id = req.target_project_id.unwrap_or(project_id);
+    let same_project = target_id == project_id;
+    if !same_project {
+        verify_project_owner(&state, auth.user_id, target_id).await?;
+    }
+
+    let mut old_ids: Vec<String> = Vec::with_capacity(req.client_ids.len());
+    for id in req.client_ids {
+        if !old_ids.contains(&id) {
+            old_ids.push(id);
+        }
+    }
+    if old_ids.is_empty() {
+        return Err(AppError::Validation("clientIds must not be empty".into()));
+    }
+    ensure_nodes_exist(&state.db, project_id, &old_ids).await?;
+
+    let default_offset = if same_project { 20.0 } else { 0.0 };
+    // A random suffix keeps concurrent duplicates of the same nodes distinct
+    let suffix = Uuid::new_v4().simple();
+    let new_ids: Vec<String> = old_ids
+        .iter()
+        .map(|id| format!("{id}-copy-{suffix}"))
+        .collect();
+
+    let mut tx = state.db.begin().await?;
+    if !same_project {
+        // Blobs are keyed by content hash, so only the asset rows need copying
+        sqlx::query(
+            "INSERT INTO assets
+             (project_id, sha256, file_name, content_type, size_bytes, width, height,
+              has_thumbnail, uploaded_by)
+             SELECT $2, a.sha256, a.file_name, a.content_type, a.size_bytes, a.width, a.height,
+                    a.has_thumbnail, a.uploaded_by
+             FROM assets a
+             WHERE a.project_id = $1 AND a.id::text IN (
+                 SELECT jsonb_array_elements_text(n.asset_ids) FROM canvas_nodes n
+                 WHERE n.project_id = $1 AND n.client_id = ANY($3) AND n.deleted_at IS NULL
+             )
+             ON CONFLICT (project_id, sha256) DO NOTHING",
+        )
+        .bind(project_id)
+        .bind(target_id)
+        .bind(&old_ids)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let nodes = sqlx::query_as::<_, CanvasNode>(
+        "WITH map AS (
+             SELECT * FROM UNNEST($3::text[], $4::text[]) AS m(old_id, new_id)
+         )
+         INSERT INTO canvas_nodes
+         (project_id, client_id, node_type, title, description, x, y, width, height,
+          status, content, file_name, generated_code, picked, parent_id, page_role,
+          tag, platform, language, ai_model, element_links, env_vars, payload, group_id,
+          asset_ids)
+         SELECT $2, m.new_id, n.node_type, n.title, n.description,
+                n.x + $5, n.y + $6, n.width, n.height,
+                n.status, n.content, n.file_name, n.generated_code, n.picked,
+                COALESCE(p.new_id, CASE WHEN $7 THEN n.parent_id END),
+                n.page_role, n.tag, n.platform, n.language, n.ai_model,
+                COALESCE((
+                    SELECT jsonb_agg(
+                        CASE WHEN t.new_id IS NULL THEN l.link
+                             ELSE jsonb_set(l.link, '{targetNodeId}', to_jsonb(t.new_id))
+                        END ORDER BY l.ord)
+                    FROM jsonb_array_elements(n.element_links) WITH ORDINALITY AS l(link, ord)
+                    LEFT JOIN map t ON t.old_id = l.link->>'targetNodeId'
+                    WHERE t.new_id IS NOT NULL OR $7
+                ), '[]'::jsonb),
+                n.env_vars,
+                CASE WHEN n.node_type <> 'env' OR NOT n.payload ? 'base'
+                          OR (b.new_id IS NULL AND $7) THEN n.payload
+                     WHEN b.new_id IS NULL THEN n.payload - 'base'
+                     ELSE jsonb_set(n.payload, '{base}', to_jsonb(b.new_id))
+                END,
+                CASE WHEN $7 THEN n.group_id END,
+                CASE WHEN $7 THEN n.asset_ids ELSE COALESCE((
+                    SELECT jsonb_agg(to_jsonb(dst.id) ORDER BY e.ord)
+                    FROM jsonb_array_elements_text(n.asset_ids) WITH ORDINALITY AS e(id, ord)
+                    JOIN assets src ON src.id::text = e.id
+                    JOIN assets dst ON dst.project_id = $2 AND dst.sha256 = src.sha256
+                ), '[]'::jsonb) END
+         FROM canvas_nodes n
+         JOIN map m ON m.old_id = n.client_id
+         LEFT JOIN map p ON p.old_id = n.parent_id
+         LEFT JOIN map b ON b.old_id = n.payload->>'base'
+         WHERE n.project_id = $1 AND n.deleted_at IS NULL
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(target_id)
+    .bind(&old_ids)
+    .bind(&new_ids)
+    .bind(req.dx.unwrap_or(default_offset))
+    .bind(req.dy.unwrap_or(default_offset))
+    .bind(same_project)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let connections = sqlx::query_as::<_, (String, String)>(
+        "WITH map AS (
+             SELECT * FROM UNNEST($3::text[], $4::text[]) AS m(old_id, new_id)
+         )
+         INSERT INTO node_connections
+         (project_id, from_client_id, to_client_id, kind, label, source_port, target_port, style)
+         SELECT $2, f.new_id, t.new_id, c.kind, c.label, c.source_port, c.target_port, c.style
+         FROM node_connections c
+         JOIN map f ON f.old_id = c.from_client_id
+         JOIN map t ON t.old_id = c.to_client_id
+         WHERE c.project_id = $1
+         RETURNING from_client_id, to_client_id",
+    )
+    .bind(project_id)
+    .bind(target_id)
+    .bind(&old_ids)
+    .bind(&new_ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let trashed = fetch_trashed_client_ids(&state.db, target_id).await?;
+    let mut connected_to: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, to) in connections {
+        connected_to.entry(from).or_default().push(to);
+    }
+    let mut by_id: HashMap<String, CanvasNode> =
+        nodes.into_iter().map(|n| (n.client_id.clone(), n)).collect();
+    let nodes = new_ids
+        .iter()
+        .filter_map(|id| by_id.remove(id))
+        .map(|n| node_to_response(n, &connected_to, &trashed))
+        .collect();
+
+    Ok((
+        StatusCode::CREATED,
+        Json(DuplicateNodesResponse {
+            project_id: target_id,
+            id_map: old_ids.into_iter().zip(new_ids).collect(),
+            nodes,
+        }),
+    ))
 }
 
 /// List all connections for a project
@@ -371,12 +768,7 @@ pub async fn list_connections(
 ) -> Result<Json<Vec<[String; 2]>>> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
 
-    let rows = sqlx::query_as::<_, (String, String)>(
-        "SELECT from_client_id, to_client_id FROM node_connections WHERE project_id = $1",
-    )
-    .bind(project_id)
-    .fetch_all(&state.db)
-    .await?;
+    let rows = fetch_live_connections(&state, project_id, None).await?;
 
     let connections = rows.into_iter().map(|(f, t)| [f, t]).collect();
     Ok(Json(connections))
@@ -389,8 +781,9 @@ pub async fn list_connections(
     params(("project_id" = Uuid, Path, description = "Project UUID")),
     request_body = ConnectNodesRequest,
     responses(
-        (status = 201, description = "Connection created"),
+        (status = 201, description = "Connection created or updated, returned under `connection`"),
         (status = 401, description = "Unauthorized"),
+        (status = 422, description = "An endpoint is not a live node, or style is not an object"),
     ),
     security(("bearer_auth" = []))
 )]
@@ -401,18 +794,30 @@ pub async fn connect_nodes(
     Json(req): Json<ConnectNodesRequest>,
 ) -> Result<(StatusCode, Json<Value>)> {
     verify_project_owner(&state, auth.user_id, project_id).await?;
+    ensure_nodes_exist(
+        &state.db,
+        project_id,
+        &[req.from_client_id.clone(), req.to_client_id.clone()],
+    )
+    .await?;
 
-    sqlx::query(
-        "INSERT INTO node_connections (project_id, from_client_id, to_client_id)
-         VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+    let mut tx = state.db.begin().await?;
+    upsert_connections(&mut tx, project_id, std::slice::from_ref(&req)).await?;
+    let connection = sqlx::query_as::<_, Connection>(
+        "SELECT * FROM node_connections
+         WHERE project_id = $1 AND from_client_id = $2 AND to_client_id = $3",
     )
     .bind(project_id)
     .bind(&req.from_client_id)
     .bind(&req.to_client_id)
-    .execute(&state.db)
+    .fetch_one(&mut *tx)
     .await?;
+    tx.commit().await?;
 
-    Ok((StatusCode::CREATED, Json(json!({ "message": "Connection created" }))))
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "message": "Connection created", "connection": connection })),
+    ))
 }
 
 /// Remove a connection between two nodes
@@ -448,6 +853,79 @@ pub async fn disconnect_nodes(
     Ok(Json(json!({ "message": "Connection removed" })))
 }
 
+/// List connections with their kind, label, ports and style
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/connections/detailed",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Connections with metadata", body = Vec<Connection>),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_connection_details(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<Connection>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let connections = fetch_live_connection_details(&state, project_id).await?;
+    Ok(Json(connections))
+}
+
+/// Update a connection's kind, label, ports or style
+#[utoipa::path(
+    patch,
+    path = "/api/projects/{project_id}/connections/{connection_id}",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("connection_id" = Uuid, Path, description = "Connection UUID"),
+    ),
+    request_body = UpdateConnectionRequest,
+    responses(
+        (status = 200, description = "Updated connection", body = Connection),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Connection not found"),
+        (status = 422, description = "style is not an object"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_connection(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, connection_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<UpdateConnectionRequest>,
+) -> Result<Json<Connection>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    if req.style.as_ref().is_some_and(|s| !s.is_object()) {
+        return Err(AppError::Validation("style: must be a JSON object".into()));
+    }
+
+    let connection = sqlx::query_as::<_, Connection>(
+        "UPDATE node_connections SET
+            kind        = COALESCE($3, kind),
+            label       = COALESCE($4, label),
+            source_port = COALESCE($5, source_port),
+            target_port = COALESCE($6, target_port),
+            style       = COALESCE($7, style)
+         WHERE project_id = $1 AND id = $2
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(connection_id)
+    .bind(req.kind)
+    .bind(req.label.as_deref())
+    .bind(req.source_port.as_deref())
+    .bind(req.target_port.as_deref())
+    .bind(&req.style)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Connection {connection_id} not found")))?;
+
+    Ok(Json(connection))
+}
+
 /// Remove a specific element link from a node by target node ID
 #[utoipa::path(
     delete,
@@ -478,7 +956,7 @@ pub async fn remove_element_link(
              FROM jsonb_array_elements(element_links) el
              WHERE el->>'targetNodeId' <> $3
          )
-         WHERE project_id = $1 AND client_id = $2",
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
     )
     .bind(project_id)
     .bind(&client_id)
@@ -490,11 +968,28 @@ pub async fn remove_element_link(
     Ok(Json(node))
 }
 
-fn node_to_response(node: CanvasNode, connected_to_map: &HashMap<String, Vec<String>>) -> CanvasNodeResponse {
-    let element_links: Vec<ElementLink> =
+/// Build the API shape of a node. Element links to trashed nodes are left
+/// out, as connections are, and come back when the target is restored.
+pub(crate) fn node_to_response(
+    node: CanvasNode,
+    connected_to_map: &HashMap<String, Vec<String>>,
+    trashed: &HashSet<String>,
+) -> CanvasNodeResponse {
+    let mut element_links: Vec<ElementLink> =
         serde_json::from_value(node.element_links).unwrap_or_default();
-    let env_vars: HashMap<String, String> =
+    element_links.retain(|link| !trashed.contains(&link.target_node_id));
+    let mut env_vars: HashMap<String, String> =
         serde_json::from_value(node.env_vars).unwrap_or_default();
+    let secret_env_vars = secrets::mask_env_vars(&mut env_vars);
+    let mut payload = node.payload;
+    let mut generated_code = node.generated_code;
+    if matches!(node.node_type, NodeType::Payment) {
+        if let Some(config) = payload.as_mut() {
+            secrets::mask_payment(config);
+        }
+        generated_code = generated_code.map(secrets::mask_payment_code);
+    }
+    let asset_ids: Vec<Uuid> = serde_json::from_value(node.asset_ids).unwrap_or_default();
     let connected_to = connected_to_map
         .get(&node.client_id)
         .cloned()
@@ -511,9 +1006,10 @@ fn node_to_response(node: CanvasNode, connected_to_map: &HashMap<String, Vec<Str
         width: node.width,
         height: node.height,
         status: node.status,
+        status_changed_at: node.status_changed_at,
         content: node.content,
         file_name: node.file_name,
-        generated_code: node.generated_code,
+        generated_code,
         picked: node.picked,
         parent_id: node.parent_id,
         page_role: node.page_role,
@@ -523,15 +1019,704 @@ fn node_to_response(node: CanvasNode, connected_to_map: &HashMap<String, Vec<Str
         ai_model: node.ai_model,
         element_links,
         env_vars,
+        secret_env_vars,
+        payload,
+        group_id: node.group_id,
+        asset_ids,
         connected_to,
+        content_hash: node.content_hash,
         created_at: node.created_at,
         updated_at: node.updated_at,
     }
 }
 
-async fn verify_project_owner(state: &AppState, user_id: Uuid, project_id: Uuid) -> Result<()> {
+/// Secret-bearing columns of a stored node
+#[derive(Default, sqlx::FromRow)]
+struct StoredSecrets {
+    env_vars: Value,
+    payload: Option<Value>,
+    generated_code: Option<String>,
+}
+
+impl StoredSecrets {
+    fn env_vars(&self) -> HashMap<String, String> {
+        serde_json::from_value(self.env_vars.clone()).unwrap_or_default()
+    }
+}
+
+/// A row a bulk save is about to overwrite
+#[derive(sqlx::FromRow)]
+struct ReplacedNode {
+    client_id: String,
+    status: NodeStatus,
+    #[sqlx(flatten)]
+    secrets: StoredSecrets,
+}
+
+/// Encrypt a payment node's secret keys in its payload and in the config JSON
+/// the editor keeps in `generatedCode`; returns the code to store
+fn seal_payment_secrets(
+    node_type: &NodeType,
+    payload: &mut Option<Value>,
+    generated_code: Option<&str>,
+    stored: &StoredSecrets,
+    secret: &str,
+) -> Result<Option<String>> {
+    if !matches!(node_type, NodeType::Payment) {
+        return Ok(generated_code.map(str::to_string));
+    }
+    if let Some(config) = payload.as_mut() {
+        secrets::seal_payment("payload", config, stored.payload.as_ref(), secret)?;
+    }
+    generated_code
+        .map(|code| secrets::seal_payment_code(code, stored.generated_code.as_deref(), secret))
+        .transpose()
+}
+
+/// Insert or replace many nodes with a single statement by unnesting
+/// per-column arrays. Replaced rows keep their id and leave the trash.
+/// Payloads are validated first, so a malformed one aborts the whole batch.
+/// Secrets are encrypted with `secret`.
+pub(crate) async fn upsert_nodes(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    nodes: &[CreateNodeRequest],
+    secret: &str,
+    lock_token: Option<Uuid>,
+) -> Result<u64> {
+    let empty_map = HashMap::<String, String>::new();
+
+    let mut payloads = nodes
+        .iter()
+        .map(|n| {
+            NodePayload::validate(&n.node_type, n.payload.as_ref()).map_err(|e| match e {
+                AppError::Validation(m) => {
+                    AppError::Validation(format!("Node '{}': {m}", n.client_id))
+                }
+                other => other,
+            })
+        })
+        .collect::<Result<Vec<Option<Value>>>>()?;
+
+    let referenced: Vec<Uuid> = nodes
+        .iter()
+        .flat_map(|n| n.asset_ids.iter().flatten().copied())
+        .collect();
+    assets::ensure_assets_exist(&mut *conn, project_id, &referenced).await?;
+
+    let client_ids: Vec<&str> = nodes.iter().map(|n| n.client_id.as_str()).collect();
+
+    // Nodes another session has locked may only be saved unchanged, which a full
+    // canvas save does for every node it did not touch
+    let guarded: HashMap<Uuid, Value> = sqlx::query_as::<_, (Uuid, Value)>(
+        "SELECT n.id, to_jsonb(n) - 'updated_at' FROM canvas_nodes n
+         JOIN node_locks l ON l.node_id = n.id
+         WHERE n.project_id = $1 AND n.client_id = ANY($2)
+           AND l.expires_at > NOW() AND l.token IS DISTINCT FROM $3",
+    )
+    .bind(project_id)
+    .bind(&client_ids)
+    .bind(lock_token)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .collect();
+
+    let current: HashMap<String, ReplacedNode> = sqlx::query_as::<_, ReplacedNode>(
+        "SELECT client_id, status, env_vars, payload, generated_code FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = ANY($2)",
+    )
+    .bind(project_id)
+    .bind(&client_ids)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .map(|row| (row.client_id.clone(), row))
+    .collect();
+    // A bulk save carries whatever status the client last saw, which may be
+    // stale (e.g. the sweeper reset a stuck node). A replaced node keeps its
+    // status rather than taking one the lifecycle does not allow.
+    let statuses: Vec<NodeStatus> = nodes
+        .iter()
+        .map(|n| {
+            let status = n.status.clone().unwrap_or(NodeStatus::Idle);
+            match current.get(&n.client_id) {
+                Some(replaced) if !replaced.status.can_become(&status) => replaced.status.clone(),
+                _ => status,
+            }
+        })
+        .collect();
+
+    // Masked secrets stand for the values of the rows being replaced
+    let no_secrets = StoredSecrets::default();
+    let mut generated_codes = Vec::with_capacity(nodes.len());
+    let mut env_vars = Vec::with_capacity(nodes.len());
+    for (n, payload) in nodes.iter().zip(payloads.iter_mut()) {
+        let stored = current.get(&n.client_id).map_or(&no_secrets, |r| &r.secrets);
+        let with_node = |e: AppError| match e {
+            AppError::Validation(m) => AppError::Validation(format!("Node '{}': {m}", n.client_id)),
+            other => other,
+        };
+        let code = seal_payment_secrets(
+            &n.node_type,
+            payload,
+            n.generated_code.as_deref(),
+            stored,
+            secret,
+        )
+        .map_err(with_node)?;
+        let vars = secrets::seal_env_vars(
+            n.env_vars.as_ref().unwrap_or(&empty_map),
+            n.secret_env_vars.as_deref(),
+            &stored.env_vars(),
+            secret,
+        )
+        .map_err(with_node)?;
+        generated_codes.push(code);
+        env_vars.push(serde_json::to_value(vars).unwrap_or_default());
+    }
+
+    let node_types: Vec<NodeType> = nodes.iter().map(|n| n.node_type.clone()).collect();
+    let titles: Vec<&str> = nodes.iter().map(|n| n.title.as_str()).collect();
+    let descriptions: Vec<&str> = nodes.iter().map(|n| n.description.as_str()).collect();
+    let xs: Vec<f64> = nodes.iter().map(|n| n.x).collect();
+    let ys: Vec<f64> = nodes.iter().map(|n| n.y).collect();
+    let widths: Vec<f64> = nodes.iter().map(|n| n.width).collect();
+    let heights: Vec<f64> = nodes.iter().map(|n| n.height).collect();
+    let contents: Vec<Option<&str>> = nodes.iter().map(|n| n.content.as_deref()).collect();
+    let file_names: Vec<Option<&str>> = nodes.iter().map(|n| n.file_name.as_deref()).collect();
+    let picked: Vec<bool> = nodes.iter().map(|n| n.picked.unwrap_or(false)).collect();
+    let parent_ids: Vec<Option<&str>> = nodes.iter().map(|n| n.parent_id.as_deref()).collect();
+    let page_roles: Vec<Option<&str>> = nodes.iter().map(|n| n.page_role.as_deref()).collect();
+    let tags: Vec<Option<&str>> = nodes.iter().map(|n| n.tag.as_deref()).collect();
+    let platforms: Vec<Option<NodePlatform>> = nodes.iter().map(|n| n.platform.clone()).collect();
+    let languages: Vec<Option<&str>> = nodes.iter().map(|n| n.language.as_deref()).collect();
+    let ai_models: Vec<Option<&str>> = nodes.iter().map(|n| n.ai_model.as_deref()).collect();
+    let linked = nodes
+        .iter()
+        .map(|n| {
+            let links = n.element_links.clone().unwrap_or_default();
+            let content = if links.is_empty() { None } else { n.content.clone() };
+            (content, links)
+        })
+        .collect();
+    let mut element_links = resolve_element_link_batch(linked).await?;
+    flag_missing_link_targets(&mut *conn, project_id, &client_ids, &mut element_links).await?;
+    let element_links: Vec<Value> = element_links
+        .iter()
+        .map(|links| serde_json::to_value(links).unwrap_or_default())
+        .collect();
+    let asset_ids: Vec<Value> = nodes
+        .iter()
+        .map(|n| serde_json::to_value(n.asset_ids.as_deref().unwrap_or(&[])).unwrap_or_default())
+        .collect();
+
+    let rows = sqlx::query(&format!(
+        "INSERT INTO canvas_nodes
+         (project_id, client_id, node_type, title, description, x, y, width, height,
+          status, content, file_name, generated_code, picked, parent_id, page_role,
+          tag, platform, language, ai_model, element_links, env_vars, payload, asset_ids)
+         SELECT $1, * FROM UNNEST(
+             $2::text[], $3::node_type[], $4::text[], $5::text[],
+             $6::float8[], $7::float8[], $8::float8[], $9::float8[],
+             $10::node_status[], $11::text[], $12::text[], $13::text[], $14::bool[],
+             $15::text[], $16::text[], $17::text[], $18::node_platform[],
+             $19::text[], $20::text[], $21::jsonb[], $22::jsonb[], $23::jsonb[],
+             $24::jsonb[]
+         )
+         ON CONFLICT (project_id, client_id) DO UPDATE SET
+             node_type      = EXCLUDED.node_type,
+             title          = EXCLUDED.title,
+             description    = EXCLUDED.description,
+             x              = EXCLUDED.x,
+             y              = EXCLUDED.y,
+             width          = EXCLUDED.width,
+             height         = EXCLUDED.height,
+             status         = EXCLUDED.status,
+             content        = EXCLUDED.content,
+             file_name      = EXCLUDED.file_name,
+             generated_code = EXCLUDED.generated_code,
+             picked         = EXCLUDED.picked,
+             parent_id      = EXCLUDED.parent_id,
+             page_role      = EXCLUDED.page_role,
+             tag            = EXCLUDED.tag,
+             platform       = EXCLUDED.platform,
+             language       = EXCLUDED.language,
+             ai_model       = EXCLUDED.ai_model,
+             element_links  = {},
+             env_vars       = EXCLUDED.env_vars,
+             payload        = EXCLUDED.payload,
+             asset_ids      = EXCLUDED.asset_ids,
+             deleted_at     = NULL",
+        with_trashed_links("EXCLUDED.element_links")
+    ))
+    .bind(project_id)
+    .bind(&client_ids)
+    .bind(&node_types)
+    .bind(&titles)
+    .bind(&descriptions)
+    .bind(&xs)
+    .bind(&ys)
+    .bind(&widths)
+    .bind(&heights)
+    .bind(&statuses)
+    .bind(&contents)
+    .bind(&file_names)
+    .bind(&generated_codes)
+    .bind(&picked)
+    .bind(&parent_ids)
+    .bind(&page_roles)
+    .bind(&tags)
+    .bind(&platforms)
+    .bind(&languages)
+    .bind(&ai_models)
+    .bind(&element_links)
+    .bind(&env_vars)
+    .bind(&payloads)
+    .bind(&asset_ids)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    groups::restore_groups_of(&mut *conn, project_id, &client_ids).await?;
+
+    if !guarded.is_empty() {
+        let ids: Vec<Uuid> = guarded.keys().copied().collect();
+        let changed: Vec<String> = sqlx::query_as::<_, (Uuid, String, Value)>(
+            "SELECT id, client_id, to_jsonb(n) - 'updated_at' FROM canvas_nodes n
+             WHERE id = ANY($1)",
+        )
+        .bind(&ids)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .filter(|(id, _, row)| guarded.get(id) != Some(row))
+        .map(|(_, client_id, _)| client_id)
+        .collect();
+        locks::ensure_none_locked(conn, project_id, &changed, lock_token).await?;
+    }
+
+    Ok(rows)
+}
+
+/// Fail with a validation error unless every client_id is a live node of the project
+pub(crate) async fn ensure_nodes_exist<'e, E>(
+    executor: E,
+    project_id: Uuid,
+    client_ids: &[String],
+) -> Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    if client_ids.is_empty() {
+        return Ok(());
+    }
+
+    let missing: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM UNNEST($2::text[]) AS ids(id)
+         WHERE NOT EXISTS (
+             SELECT 1 FROM canvas_nodes n
+             WHERE n.project_id = $1 AND n.client_id = ids.id AND n.deleted_at IS NULL
+         )",
+    )
+    .bind(project_id)
+    .bind(client_ids)
+    .fetch_all(executor)
+    .await?;
+
+    if !missing.is_empty() {
+        return Err(AppError::Validation(format!(
+            "Unknown node(s): {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Check each link's selector against its node's HTML and record the type of
+/// the element it matches. A selector that does not resolve is flagged rather
+/// than rejected: links break whenever the HTML is edited, and the
+/// broken-links report is where they are surfaced.
+async fn resolve_element_link_batch(
+    batch: Vec<(Option<String>, Vec<ElementLink>)>,
+) -> Result<Vec<Vec<ElementLink>>> {
+    // Parsing designs is CPU-bound
+    tokio::task::spawn_blocking(move || {
+        batch
+            .into_iter()
+            .map(|(content, mut links)| {
+                if links.is_empty() {
+                    return links;
+                }
+                let design = Design::parse(content.as_deref());
+                for link in &mut links {
+                    match design.resolve(&link.selector) {
+                        Ok(element_type) => {
+                            link.element_type = Some(element_type.to_string());
+                            link.problem = None;
+                        }
+                        Err(problem) => link.problem = Some(problem.into()),
+                    }
+                }
+                links
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| AppError::Internal(anyhow::anyhow!("Link check failed: {e}")))
+}
+
+async fn resolve_element_links(
+    content: Option<String>,
+    links: Vec<ElementLink>,
+) -> Result<Vec<ElementLink>> {
+    let mut resolved = resolve_element_link_batch(vec![(content, links)]).await?;
+    Ok(resolved.pop().unwrap_or_default())
+}
+
+/// Flag links whose target node does not exist, live or in the trash. Like a
+/// selector that stops resolving, this is not a reason to reject the write.
+/// `written` are the client ids saved along with the links, which may be
+/// linked to before they exist.
+async fn flag_missing_link_targets<'e, E>(
+    executor: E,
+    project_id: Uuid,
+    written: &[&str],
+    batch: &mut [Vec<ElementLink>],
+) -> Result<()>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let targets: Vec<&str> = batch
+        .iter()
+        .flatten()
+        .map(|l| l.target_node_id.as_str())
+        .filter(|target| !written.contains(target))
+        .collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+    let existing: HashSet<String> = sqlx::query_scalar(
+        "SELECT client_id FROM canvas_nodes WHERE project_id = $1 AND client_id = ANY($2)",
+    )
+    .bind(project_id)
+    .bind(&targets)
+    .fetch_all(executor)
+    .await?
+    .into_iter()
+    .collect();
+
+    for link in batch.iter_mut().flatten() {
+        let target = link.target_node_id.as_str();
+        if !written.contains(&target) && !existing.contains(target) {
+            link.problem = Some(BrokenLinkReason::MissingTarget);
+        }
+    }
+    Ok(())
+}
+
+/// Primary key of a live node, or not found
+pub(crate) async fn live_node_id<'e, E>(
+    executor: E,
+    project_id: Uuid,
+    client_id: &str,
+) -> Result<Uuid>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    sqlx::query_scalar(
+        "SELECT id FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(client_id)
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))
+}
+
+/// Drop element links whose target node no longer exists in its project
+pub(crate) async fn strip_dangling_element_links(
+    conn: &mut PgConnection,
+    project_ids: &[Uuid],
+) -> Result<u64> {
+    let rows = sqlx::query(
+        "UPDATE canvas_nodes n
+         SET element_links = (
+             SELECT COALESCE(jsonb_agg(el), '[]'::jsonb)
+             FROM jsonb_array_elements(n.element_links) el
+             WHERE EXISTS (
+                 SELECT 1 FROM canvas_nodes t
+                 WHERE t.project_id = n.project_id AND t.client_id = el->>'targetNodeId'
+             )
+         )
+         WHERE n.project_id = ANY($1)
+           AND EXISTS (
+               SELECT 1 FROM jsonb_array_elements(n.element_links) el
+               WHERE NOT EXISTS (
+                   SELECT 1 FROM canvas_nodes t
+                   WHERE t.project_id = n.project_id AND t.client_id = el->>'targetNodeId'
+               )
+           )",
+    )
+    .bind(project_ids)
+    .execute(conn)
+    .await?
+    .rows_affected();
+
+    Ok(rows)
+}
+
+/// Create connections, or update the metadata of pairs that are already
+/// connected. Fields left as `None` keep their current value.
+pub(crate) async fn upsert_connections(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    connections: &[ConnectNodesRequest],
+) -> Result<()> {
+    if let Some(c) = connections
+        .iter()
+        .find(|c| c.style.as_ref().is_some_and(|s| !s.is_object()))
+    {
+        return Err(AppError::Validation(format!(
+            "style: connection {} -> {} must have a JSON object style",
+            c.from_client_id, c.to_client_id
+        )));
+    }
+
+    let from: Vec<&str> = connections.iter().map(|c| c.from_client_id.as_str()).collect();
+    let to: Vec<&str> = connections.iter().map(|c| c.to_client_id.as_str()).collect();
+    let kinds: Vec<Option<ConnectionKind>> = connections.iter().map(|c| c.kind).collect();
+    let labels: Vec<Option<&str>> = connections.iter().map(|c| c.label.as_deref()).collect();
+    let source_ports: Vec<Option<&str>> =
+        connections.iter().map(|c| c.source_port.as_deref()).collect();
+    let target_ports: Vec<Option<&str>> =
+        connections.iter().map(|c| c.target_port.as_deref()).collect();
+    let styles: Vec<Option<Value>> = connections.iter().map(|c| c.style.clone()).collect();
+
+    let with_metadata = "WITH input AS (
+             SELECT * FROM UNNEST(
+                 $2::text[], $3::text[], $4::connection_kind[], $5::text[], $6::text[],
+                 $7::text[], $8::jsonb[]
+             ) AS u(from_id, to_id, kind, label, source_port, target_port, style)
+         )";
+
+    // Existing pairs only change where metadata was given
+    sqlx::query(&format!(
+        "{with_metadata}
+         UPDATE node_connections c SET
+             kind        = COALESCE(u.kind, c.kind),
+             label       = COALESCE(u.label, c.label),
+             source_port = COALESCE(u.source_port, c.source_port),
+             target_port = COALESCE(u.target_port, c.target_port),
+             style       = COALESCE(u.style, c.style)
+         FROM input u
+         WHERE c.project_id = $1 AND c.from_client_id = u.from_id AND c.to_client_id = u.to_id
+           AND (u.kind IS NOT NULL OR u.label IS NOT NULL OR u.source_port IS NOT NULL
+                OR u.target_port IS NOT NULL OR u.style IS NOT NULL)"
+    ))
+    .bind(project_id)
+    .bind(&from)
+    .bind(&to)
+    .bind(&kinds)
+    .bind(&labels)
+    .bind(&source_ports)
+    .bind(&target_ports)
+    .bind(&styles)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(&format!(
+        "{with_metadata}
+         INSERT INTO node_connections
+         (project_id, from_client_id, to_client_id, kind, label, source_port, target_port, style)
+         SELECT $1, u.from_id, u.to_id, COALESCE(u.kind, 'data_flow'), u.label,
+                u.source_port, u.target_port, COALESCE(u.style, '{{}}')
+         FROM input u
+         ON CONFLICT DO NOTHING"
+    ))
+    .bind(project_id)
+    .bind(&from)
+    .bind(&to)
+    .bind(&kinds)
+    .bind(&labels)
+    .bind(&source_ports)
+    .bind(&target_ports)
+    .bind(&styles)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// `links` plus the stored links of the updated `canvas_nodes` row whose target
+/// is in the trash. Responses leave those out, so a client writing back what
+/// it was sent must not drop them before the target is restored.
+fn with_trashed_links(links: &str) -> String {
+    format!(
+        "{links} || COALESCE((
+             SELECT jsonb_agg(el) FROM jsonb_array_elements(canvas_nodes.element_links) el
+             WHERE EXISTS (
+                 SELECT 1 FROM canvas_nodes t
+                 WHERE t.project_id = canvas_nodes.project_id
+                   AND t.client_id = el->>'targetNodeId' AND t.deleted_at IS NOT NULL
+             )
+             AND NOT EXISTS (
+                 SELECT 1 FROM jsonb_array_elements({links}) k
+                 WHERE k->>'targetNodeId' = el->>'targetNodeId'
+                   AND k->>'selector' = el->>'selector'
+             )
+         ), '[]'::jsonb)"
+    )
+}
+
+/// Rectangle covered by a node; must match the expression of the
+/// `idx_canvas_nodes_bbox` index for viewport queries to use it
+const NODE_BOX: &str = "box(point(x, y), point(x + width, y + height))";
+
+/// A viewport rectangle parsed from `bbox=x1,y1,x2,y2`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BoundingBox {
+    x1: f64,
+    y1: f64,
+    x2: f64,
+    y2: f64,
+}
+
+impl BoundingBox {
+    /// Corners may be given in any order
+    pub(crate) fn parse(raw: &str) -> Result<Self> {
+        let coords: Vec<f64> = raw
+            .split(',')
+            .map(|c| c.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+            .collect::<Option<_>>()
+            .filter(|c: &Vec<f64>| c.len() == 4)
+            .ok_or_else(|| {
+                AppError::Validation("bbox: expected four numbers x1,y1,x2,y2".into())
+            })?;
+
+        Ok(Self {
+            x1: coords[0].min(coords[2]),
+            y1: coords[1].min(coords[3]),
+            x2: coords[0].max(coords[2]),
+            y2: coords[1].max(coords[3]),
+        })
+    }
+
+    /// Append an intersection test against `canvas_nodes` to an open WHERE clause
+    pub(crate) fn push_filter(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        qb.push(format!(" AND {NODE_BOX} && box(point("))
+            .push_bind(self.x1)
+            .push(", ")
+            .push_bind(self.y1)
+            .push("), point(")
+            .push_bind(self.x2)
+            .push(", ")
+            .push_bind(self.y2)
+            .push("))");
+    }
+}
+
+/// Every column except the heavy `content` and `generated_code` bodies
+const NODE_SUMMARY_COLUMNS: &str = "id, project_id, client_id, node_type, title, description, \
+     x, y, width, height, status, status_changed_at, file_name, picked, parent_id, page_role, tag, \
+     platform, language, ai_model, element_links, env_vars, payload, group_id, asset_ids, \
+     content_hash, created_at, updated_at, deleted_at";
+
+/// Response fields selectable through `fields=`
+const NODE_FIELDS: &[&str] = &[
+    "id",
+    "clientId",
+    "type",
+    "title",
+    "description",
+    "x",
+    "y",
+    "width",
+    "height",
+    "status",
+    "statusChangedAt",
+    "content",
+    "fileName",
+    "generatedCode",
+    "picked",
+    "parentId",
+    "pageRole",
+    "tag",
+    "platform",
+    "language",
+    "aiModel",
+    "elementLinks",
+    "envVars",
+    "secretEnvVars",
+    "payload",
+    "groupId",
+    "assetIds",
+    "connectedTo",
+    "contentHash",
+    "createdAt",
+    "updatedAt",
+];
+
+/// Subset of node response fields requested by the client
+pub(crate) struct NodeFieldSet {
+    fields: Option<HashSet<&'static str>>,
+}
+
+impl NodeFieldSet {
+    /// Parse a `fields=` list; `summary` drops the heavy bodies from whatever
+    /// was selected. `id` and `clientId` are always included.
+    pub(crate) fn parse(fields: Option<&str>, summary: bool) -> Result<Self> {
+        let mut selected = match fields {
+            None => None,
+            Some(raw) => {
+                let mut set = HashSet::from(["id", "clientId"]);
+                for name in raw.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                    let field = NODE_FIELDS.iter().find(|f| **f == name).ok_or_else(|| {
+                        AppError::Validation(format!("Unknown node field '{name}'"))
+                    })?;
+                    set.insert(*field);
+                }
+                Some(set)
+            }
+        };
+
+        if summary {
+            let set = selected.get_or_insert_with(|| NODE_FIELDS.iter().copied().collect());
+            set.remove("content");
+            set.remove("generatedCode");
+        }
+
+        Ok(Self { fields: selected })
+    }
+
+    /// Column list to select; skips the bodies when neither is requested
+    pub(crate) fn columns(&self) -> &'static str {
+        match &self.fields {
+            Some(f) if !f.contains("content") && !f.contains("generatedCode") => {
+                NODE_SUMMARY_COLUMNS
+            }
+            _ => "*",
+        }
+    }
+
+    pub(crate) fn apply(&self, node: CanvasNodeResponse) -> Value {
+        let value = serde_json::to_value(node).unwrap_or_default();
+        match (&self.fields, value) {
+            (Some(fields), Value::Object(map)) => Value::Object(
+                map.into_iter()
+                    .filter(|(k, _)| fields.contains(k.as_str()))
+                    .collect(),
+            ),
+            (_, value) => value,
+        }
+    }
+}
+
+pub(crate) async fn verify_project_owner(state: &AppState, user_id: Uuid, project_id: Uuid) -> Result<()> {
     let exists: Option<bool> = sqlx::query_scalar(
-        "SELECT EXISTS(SELECT 1 FROM projects WHERE id = $1 AND user_id = $2)",
+        "SELECT EXISTS(
+             SELECT 1 FROM projects WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+         )",
     )
     .bind(project_id)
     .bind(user_id)
@@ -546,13 +1731,14 @@ async fn verify_project_owner(state: &AppState, user_id: Uuid, project_id: Uuid)
     Ok(())
 }
 
-async fn fetch_node_response(
+pub(crate) async fn fetch_node_response(
     state: &AppState,
     project_id: Uuid,
     client_id: &str,
 ) -> Result<CanvasNodeResponse> {
     let node = sqlx::query_as::<_, CanvasNode>(
-        "SELECT * FROM canvas_nodes WHERE project_id = $1 AND client_id = $2",
+        "SELECT * FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
     )
     .bind(project_id)
     .bind(client_id)
@@ -561,17 +1747,120 @@ async fn fetch_node_response(
     .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))?;
 
     let conn_rows = sqlx::query_as::<_, (String, String)>(
-        "SELECT from_client_id, to_client_id FROM node_connections WHERE project_id = $1 AND from_client_id = $2",
+        "SELECT c.from_client_id, c.to_client_id FROM node_connections c
+         WHERE c.project_id = $1 AND c.from_client_id = $2
+           AND NOT EXISTS (
+               SELECT 1 FROM canvas_nodes n
+               WHERE n.project_id = c.project_id
+                 AND n.client_id = c.to_client_id
+                 AND n.deleted_at IS NOT NULL
+           )",
     )
     .bind(project_id)
     .bind(client_id)
     .fetch_all(&state.db)
     .await?;
 
+    let trashed = fetch_trashed_client_ids(&state.db, project_id).await?;
     let mut map: HashMap<String, Vec<String>> = HashMap::new();
     for (from, to) in conn_rows {
         map.entry(from).or_default().push(to);
     }
 
-    Ok(node_to_response(node, &map))
+    Ok(node_to_response(node, &map, &trashed))
+}
+
+/// Live connections with both endpoints inside a viewport rectangle
+pub(crate) async fn fetch_connections_in_box(
+    state: &AppState,
+    project_id: Uuid,
+    bbox: &BoundingBox,
+) -> Result<Vec<Connection>> {
+    let mut qb = QueryBuilder::new(
+        "WITH visible AS (
+             SELECT client_id FROM canvas_nodes
+             WHERE project_id = ",
+    );
+    qb.push_bind(project_id).push(" AND deleted_at IS NULL");
+    bbox.push_filter(&mut qb);
+    qb.push(
+        ")
+         SELECT c.* FROM node_connections c
+         WHERE c.project_id = ",
+    )
+    .push_bind(project_id)
+    .push(
+        " AND c.from_client_id IN (SELECT client_id FROM visible)
+          AND c.to_client_id IN (SELECT client_id FROM visible)",
+    );
+
+    let rows = qb.build_query_as().fetch_all(&state.db).await?;
+    Ok(rows)
+}
+
+/// Connections of a project, excluding any that touch a trashed node; only
+/// those of `kinds` when given
+pub(crate) async fn fetch_live_connections(
+    state: &AppState,
+    project_id: Uuid,
+    kinds: Option<&[ConnectionKind]>,
+) -> Result<Vec<(String, String)>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "WITH trashed AS (
+             SELECT client_id FROM canvas_nodes
+             WHERE project_id = $1 AND deleted_at IS NOT NULL
+         )
+         SELECT c.from_client_id, c.to_client_id FROM node_connections c
+         WHERE c.project_id = $1
+           AND ($2::connection_kind[] IS NULL OR c.kind = ANY($2))
+           AND c.from_client_id NOT IN (SELECT client_id FROM trashed)
+           AND c.to_client_id NOT IN (SELECT client_id FROM trashed)",
+    )
+    .bind(project_id)
+    .bind(kinds)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Client ids of the project's nodes that sit in the trash
+pub(crate) async fn fetch_trashed_client_ids<'e, E>(
+    executor: E,
+    project_id: Uuid,
+) -> Result<HashSet<String>>
+where
+    E: sqlx::PgExecutor<'e>,
+{
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT client_id FROM canvas_nodes WHERE project_id = $1 AND deleted_at IS NOT NULL",
+    )
+    .bind(project_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Like [`fetch_live_connections`], with each connection's metadata
+pub(crate) async fn fetch_live_connection_details(
+    state: &AppState,
+    project_id: Uuid,
+) -> Result<Vec<Connection>> {
+    let rows = sqlx::query_as::<_, Connection>(
+        "WITH trashed AS (
+             SELECT client_id FROM canvas_nodes
+             WHERE project_id = $1 AND deleted_at IS NOT NULL
+         )
+         SELECT c.* FROM node_connections c
+         WHERE c.project_id = $1
+           AND c.from_client_id NOT IN (SELECT client_id FROM trashed)
+           AND c.to_client_id NOT IN (SELECT client_id FROM trashed)
+         ORDER BY c.created_at, c.id",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(rows)
 }
@@ -0,0 +1,327 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use similar::TextDiff;
+use sqlx::{FromRow, PgConnection};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::{
+        locks,
+        nodes::{fetch_node_response, live_node_id, verify_project_owner},
+    },
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::{
+        canvas_node::{CanvasNodeResponse, NodeStatus, NodeType},
+        node_revision::{
+            FieldDiff, ListRevisionsQuery, NodeRevisionSummary, RevisionDiff, RevisionDiffQuery,
+            RevisionFields,
+        },
+        node_payload::NodePayload,
+    },
+    pagination::page_limit,
+    secrets,
+    state::AppState,
+};
+
+/// A live node row locked for the rest of the transaction
+#[derive(Debug, FromRow)]
+pub(crate) struct LockedNode {
+    pub id: Uuid,
+    pub node_type: NodeType,
+    pub status: NodeStatus,
+    pub updated_at: DateTime<Utc>,
+    #[sqlx(flatten)]
+    pub fields: RevisionFields,
+}
+
+/// List a node's revisions, newest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/revisions",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ListRevisionsQuery,
+    ),
+    responses(
+        (status = 200, description = "Revisions, newest first", body = Vec<NodeRevisionSummary>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project or node not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_revisions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(query): Query<ListRevisionsQuery>,
+) -> Result<Json<Vec<NodeRevisionSummary>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let node_id = live_node_id(&state.db, project_id, &client_id).await?;
+
+    let revisions = sqlx::query_as::<_, NodeRevisionSummary>(
+        "SELECT * FROM (
+             SELECT r.revision, r.author_id, u.display_name AS author_name, r.created_at,
+                    CASE WHEN LAG(r.revision) OVER w IS NULL THEN '{}'::text[]
+                    ELSE ARRAY_REMOVE(ARRAY[
+                        CASE WHEN r.title IS DISTINCT FROM LAG(r.title) OVER w
+                             THEN 'title' END,
+                        CASE WHEN r.description IS DISTINCT FROM LAG(r.description) OVER w
+                             THEN 'description' END,
+                        CASE WHEN r.content IS DISTINCT FROM LAG(r.content) OVER w
+                             THEN 'content' END,
+                        CASE WHEN r.generated_code IS DISTINCT FROM LAG(r.generated_code) OVER w
+                             THEN 'generatedCode' END
+                    ], NULL) END AS changed_fields
+             FROM node_revisions r
+             LEFT JOIN users u ON u.id = r.author_id
+             WHERE r.node_id = $1
+             WINDOW w AS (ORDER BY r.revision)
+         ) history
+         WHERE $2::int IS NULL OR revision < $2
+         ORDER BY revision DESC
+         LIMIT $3",
+    )
+    .bind(node_id)
+    .bind(query.before)
+    .bind(page_limit(query.limit))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(revisions))
+}
+
+/// Unified diff of each tracked field between two revisions
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/nodes/{client_id}/revisions/diff",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        RevisionDiffQuery,
+    ),
+    responses(
+        (status = 200, description = "Per-field unified diffs", body = RevisionDiff),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project, node or revision not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn diff_revisions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+    Query(query): Query<RevisionDiffQuery>,
+) -> Result<Json<RevisionDiff>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+    let (node_id, node_type) = sqlx::query_as::<_, (Uuid, NodeType)>(
+        "SELECT id, node_type FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(&client_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))?;
+
+    let to = match query.to {
+        Some(to) => to,
+        None => sqlx::query_scalar::<_, Option<i32>>(
+            "SELECT MAX(revision) FROM node_revisions WHERE node_id = $1",
+        )
+        .bind(node_id)
+        .fetch_one(&state.db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' has no revisions")))?,
+    };
+    let mut conn = state.db.acquire().await?;
+    let mut old = fetch_revision(&mut conn, node_id, query.from).await?;
+    let mut new = fetch_revision(&mut conn, node_id, to).await?;
+    // Revisions hold payment secrets as stored, and older ones in plaintext
+    if matches!(node_type, NodeType::Payment) {
+        for fields in [&mut old, &mut new] {
+            fields.generated_code = fields.generated_code.take().map(secrets::mask_payment_code);
+        }
+    }
+
+    let fields = old
+        .named()
+        .into_iter()
+        .zip(new.named())
+        .filter(|((_, a), (_, b))| a != b)
+        .map(|((field, a), (_, b))| FieldDiff {
+            field: field.to_string(),
+            diff: TextDiff::from_lines(a, b)
+                .unified_diff()
+                .header(&format!("{field}@{}", query.from), &format!("{field}@{to}"))
+                .to_string(),
+        })
+        .collect();
+
+    Ok(Json(RevisionDiff {
+        from: query.from,
+        to,
+        fields,
+    }))
+}
+
+/// Restore a node's tracked fields from an earlier revision. Nodes whose
+/// payload is mirrored in their code get it re-derived from the restored code.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/revisions/{revision}/revert",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("revision" = i32, Path, description = "Revision to restore"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    responses(
+        (status = 200, description = "Reverted node; the revert is recorded as a new revision", body = CanvasNodeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project, node or revision not found"),
+        (status = 409, description = "Another session holds the edit lock"),
+        (status = 422, description = "The revision's code does not hold a valid payload"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn revert_revision(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, client_id, revision)): Path<(Uuid, String, i32)>,
+) -> Result<Json<CanvasNodeResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let node = lock_node(&mut tx, project_id, &client_id).await?;
+    locks::ensure_unlocked(&mut tx, node.id, &client_id, lock_token).await?;
+    let mut target = fetch_revision(&mut tx, node.id, revision).await?;
+    if matches!(node.node_type, NodeType::Payment) {
+        if let Some(code) = target.generated_code.as_deref() {
+            let secret = &state.cfg.api_key_encryption_secret;
+            if let Some(sealed) = secrets::seal_stored_payment_code(code, secret)? {
+                target.generated_code = Some(sealed);
+            }
+        }
+    }
+
+    // Derived from the sealed code, so payment secrets stay sealed
+    let derive_payload = NodePayload::mirrored_in_code(&node.node_type);
+    let payload = if derive_payload {
+        let code = target.generated_code.as_deref();
+        NodePayload::from_code(&node.node_type, code).map_err(|e| match e {
+            AppError::Validation(m) => AppError::Validation(format!("Revision {revision}: {m}")),
+            other => other,
+        })?
+    } else {
+        None
+    };
+
+    sqlx::query(
+        "UPDATE canvas_nodes
+         SET title = $2, description = $3, content = $4, generated_code = $5,
+             payload = CASE WHEN $6 THEN $7 ELSE payload END
+         WHERE id = $1",
+    )
+    .bind(node.id)
+    .bind(&target.title)
+    .bind(&target.description)
+    .bind(target.content.as_deref())
+    .bind(target.generated_code.as_deref())
+    .bind(derive_payload)
+    .bind(payload)
+    .execute(&mut *tx)
+    .await?;
+    record_revision(&mut tx, &node, &target, auth.user_id).await?;
+    tx.commit().await?;
+
+    let node = fetch_node_response(&state, project_id, &client_id).await?;
+    Ok(Json(node))
+}
+
+/// Lock a live node's row and read its tracked fields
+pub(crate) async fn lock_node(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    client_id: &str,
+) -> Result<LockedNode> {
+    sqlx::query_as::<_, LockedNode>(
+        "SELECT id, node_type, status, updated_at, title, description, content, generated_code
+         FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL
+         FOR UPDATE",
+    )
+    .bind(project_id)
+    .bind(client_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))
+}
+
+/// Append a revision when `after` differs from the locked node's fields.
+/// The first tracked edit of a node also stores its prior state as an
+/// authorless baseline so it can be reverted to.
+pub(crate) async fn record_revision(
+    conn: &mut PgConnection,
+    node: &LockedNode,
+    after: &RevisionFields,
+    author_id: Uuid,
+) -> Result<()> {
+    if node.fields == *after {
+        return Ok(());
+    }
+
+    let before = &node.fields;
+    sqlx::query(
+        "INSERT INTO node_revisions
+             (node_id, revision, title, description, content, generated_code, created_at)
+         SELECT $1, 1, $2, $3, $4, $5, $6
+         WHERE NOT EXISTS (SELECT 1 FROM node_revisions WHERE node_id = $1)",
+    )
+    .bind(node.id)
+    .bind(&before.title)
+    .bind(&before.description)
+    .bind(before.content.as_deref())
+    .bind(before.generated_code.as_deref())
+    .bind(node.updated_at)
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO node_revisions
+             (node_id, revision, title, description, content, generated_code, author_id)
+         SELECT $1, MAX(revision) + 1, $2, $3, $4, $5, $6
+         FROM node_revisions WHERE node_id = $1",
+    )
+    .bind(node.id)
+    .bind(&after.title)
+    .bind(&after.description)
+    .bind(after.content.as_deref())
+    .bind(after.generated_code.as_deref())
+    .bind(author_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn fetch_revision(
+    conn: &mut PgConnection,
+    node_id: Uuid,
+    revision: i32,
+) -> Result<RevisionFields> {
+    sqlx::query_as::<_, RevisionFields>(
+        "SELECT title, description, content, generated_code FROM node_revisions
+         WHERE node_id = $1 AND revision = $2",
+    )
+    .bind(node_id)
+    .bind(revision)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Revision {revision} not found")))
+}
@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+use crate::{
+    error::{AppError, Result},
+    middleware::auth::AuthUser,
+    models::search::{SearchQuery, SearchResult},
+    state::AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 20;
+const MAX_LIMIT: i64 = 100;
+
+/// Characters of a body that are headlined; hits past this are still found
+/// by the index but their snippet shows the start of the body
+const SNIPPET_SOURCE_CHARS: i32 = 20_000;
+/// Private-use characters marking hits until the snippet has been escaped
+const HIT_START: char = '\u{E000}';
+const HIT_END: char = '\u{E001}';
+
+/// Full-text search across the caller's projects and nodes
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Ranked search results", body = Vec<SearchResult>),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Empty search query"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn search(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<SearchQuery>,
+) -> Result<Json<Vec<SearchResult>>> {
+    let q = params.q.trim();
+    if q.is_empty() {
+        return Err(AppError::Validation("Search query cannot be empty".into()));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    // Headlines are computed only for the page of hits that survives the LIMIT,
+    // over a bounded, tag-stripped prefix of each body
+    let results = sqlx::query_as::<_, SearchResult>(
+        "WITH q AS (SELECT websearch_to_tsquery('english', $2) AS query),
+         hits AS (
+             SELECT 'project' AS kind, p.id AS project_id, NULL::text AS client_id,
+                    NULL::node_type AS node_type, NULL::node_platform AS platform,
+                    p.name AS title, concat_ws(' ', p.name, p.description) AS body,
+                    ts_rank(p.search_vector, q.query) AS rank
+             FROM projects p, q
+             WHERE p.user_id = $1 AND p.deleted_at IS NULL
+               AND p.search_vector @@ q.query
+               AND ($3::uuid IS NULL OR p.id = $3)
+               AND $4::node_type IS NULL AND $5::node_platform IS NULL
+             UNION ALL
+             SELECT 'node', n.project_id, n.client_id, n.node_type, n.platform, n.title,
+                    concat_ws(' ', n.title, n.description, n.content, n.generated_code),
+                    ts_rank(n.search_vector, q.query)
+             FROM canvas_nodes n
+             JOIN projects p ON p.id = n.project_id, q
+             WHERE p.user_id = $1 AND p.deleted_at IS NULL AND n.deleted_at IS NULL
+               AND n.search_vector @@ q.query
+               AND ($3::uuid IS NULL OR n.project_id = $3)
+               AND ($4::node_type IS NULL OR n.node_type = $4)
+               AND ($5::node_platform IS NULL OR n.platform = $5)
+             ORDER BY rank DESC
+             LIMIT $6
+         )
+         SELECT h.kind, h.project_id, h.client_id, h.node_type, h.platform, h.title,
+                ts_headline(
+                    'english',
+                    regexp_replace(
+                        translate(left(h.body, $7), $8, ''), '<[^>]*>', ' ', 'g'
+                    ),
+                    q.query,
+                    $9
+                ) AS snippet,
+                h.rank
+         FROM hits h, q
+         ORDER BY h.rank DESC",
+    )
+    .bind(auth.user_id)
+    .bind(q)
+    .bind(params.project_id)
+    .bind(params.node_type)
+    .bind(params.platform)
+    .bind(limit)
+    .bind(SNIPPET_SOURCE_CHARS)
+    .bind(format!("{HIT_START}{HIT_END}"))
+    .bind(format!(
+        "StartSel={HIT_START}, StopSel={HIT_END}, MaxFragments=2, MaxWords=20, MinWords=5"
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    let results = results
+        .into_iter()
+        .map(|r| SearchResult {
+            snippet: render_snippet(&r.snippet),
+            ..r
+        })
+        .collect();
+    Ok(Json(results))
+}
+
+/// Escape a headline for use as HTML, then turn the hit markers into `<mark>`
+fn render_snippet(headline: &str) -> String {
+    let mut out = String::with_capacity(headline.len() + 32);
+    for c in headline.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            HIT_START => out.push_str("<mark>"),
+            HIT_END => out.push_str("</mark>"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::nodes::verify_project_owner,
+    middleware::auth::AuthUser,
+    models::canvas_node::NodeType,
+    models::secret::{RevealedSecret, SecretReveal, SecretRevealsQuery},
+    pagination::page_limit,
+    secrets,
+    state::AppState,
+};
+
+/// Secret-bearing columns of a live node
+#[derive(sqlx::FromRow)]
+struct SecretSource {
+    id: Uuid,
+    node_type: NodeType,
+    env_vars: Value,
+    payload: Option<Value>,
+    generated_code: Option<String>,
+}
+
+/// Decrypt one secret of a node; every call is recorded in the audit log
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/secrets/{name}/reveal",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+        ("name" = String, Path, description = "`env.<NAME>`, `payment.secretKey` or `payment.webhookSecret`"),
+    ),
+    responses(
+        (status = 200, description = "Plaintext of the secret", body = RevealedSecret),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project, node or secret not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn reveal_secret(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id, name)): Path<(Uuid, String, String)>,
+) -> Result<Json<RevealedSecret>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let node = sqlx::query_as::<_, SecretSource>(
+        "SELECT id, node_type, env_vars, payload, generated_code FROM canvas_nodes
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NULL",
+    )
+    .bind(project_id)
+    .bind(&client_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' not found")))?;
+
+    let stored = stored_secret(&node, &name)
+        .ok_or_else(|| AppError::NotFound(format!("Node '{client_id}' has no secret '{name}'")))?;
+    let value = secrets::open(&stored, &state.cfg.api_key_encryption_secret)?;
+
+    record_reveals(
+        &state,
+        auth.user_id,
+        project_id,
+        (node.id, &client_id),
+        std::slice::from_ref(&name),
+    )
+    .await?;
+
+    Ok(Json(RevealedSecret { name, value }))
+}
+
+/// Audit plaintext reads of a node's secrets
+pub(crate) async fn record_reveals(
+    state: &AppState,
+    user_id: Uuid,
+    project_id: Uuid,
+    (node_id, client_id): (Uuid, &str),
+    names: &[String],
+) -> Result<()> {
+    if names.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        "INSERT INTO secret_reveals (project_id, node_id, client_id, secret_name, user_id)
+         SELECT $1, $2, $3, name, $5 FROM UNNEST($4::text[]) AS name",
+    )
+    .bind(project_id)
+    .bind(node_id)
+    .bind(client_id)
+    .bind(names)
+    .bind(user_id)
+    .execute(&state.db)
+    .await?;
+    tracing::info!(
+        %user_id,
+        %project_id,
+        client_id,
+        secrets = ?names,
+        "Revealed node secrets"
+    );
+    Ok(())
+}
+
+/// The stored form of a named secret: an encrypted env var, or a payment key
+/// from the payload, falling back to the editor's config in `generatedCode`
+fn stored_secret(node: &SecretSource, name: &str) -> Option<String> {
+    if let Some(key) = name.strip_prefix("env.") {
+        let env_vars: HashMap<String, String> =
+            serde_json::from_value(node.env_vars.clone()).unwrap_or_default();
+        return env_vars.get(key).filter(|v| secrets::is_sealed(v)).cloned();
+    }
+
+    let field = name.strip_prefix("payment.")?;
+    if !matches!(node.node_type, NodeType::Payment)
+        || !secrets::PAYMENT_SECRET_FIELDS.contains(&field)
+    {
+        return None;
+    }
+    let code = node
+        .generated_code
+        .as_deref()
+        .and_then(|c| serde_json::from_str::<Value>(c).ok());
+    let stored = [node.payload.as_ref(), code.as_ref()]
+        .into_iter()
+        .flatten()
+        .find_map(|config| config.get(field)?.as_str().filter(|v| !v.is_empty()))
+        .map(str::to_string);
+    stored
+}
+
+/// List reveals of the project's secrets, newest first
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/secret-reveals",
+    params(("project_id" = Uuid, Path, description = "Project UUID"), SecretRevealsQuery),
+    responses(
+        (status = 200, description = "Audited secret reveals", body = Vec<SecretReveal>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_secret_reveals(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<SecretRevealsQuery>,
+) -> Result<Json<Vec<SecretReveal>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let reveals = sqlx::query_as::<_, SecretReveal>(
+        "SELECT r.client_id, r.secret_name, r.user_id, u.display_name AS user_name,
+                r.revealed_at
+         FROM secret_reveals r
+         LEFT JOIN users u ON u.id = r.user_id
+         WHERE r.project_id = $1 AND ($2::text IS NULL OR r.client_id = $2)
+         ORDER BY r.revealed_at DESC, r.id
+         LIMIT $3",
+    )
+    .bind(project_id)
+    .bind(query.client_id.as_deref())
+    .bind(page_limit(query.limit))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(reveals))
+}
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::{
+        groups,
+        nodes::{fetch_node_response, verify_project_owner},
+    },
+    middleware::auth::AuthUser,
+    models::{
+        canvas_node::{CanvasNodeResponse, TrashedNode},
+        project::Project,
+    },
+    state::AppState,
+};
+
+/// List the caller's trashed projects
+#[utoipa::path(
+    get,
+    path = "/api/trash/projects",
+    responses(
+        (status = 200, description = "Trashed projects, most recently deleted first", body = Vec<Project>),
+        (status = 401, description = "Unauthorized"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_trashed_projects(
+    State(state): State<AppState>,
+    auth: AuthUser,
+) -> Result<Json<Vec<Project>>> {
+    let projects = sqlx::query_as::<_, Project>(
+        "SELECT * FROM projects
+         WHERE user_id = $1 AND deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC",
+    )
+    .bind(auth.user_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(projects))
+}
+
+/// Restore a trashed project
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/restore",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Restored project", body = Project),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found in trash"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn restore_project(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Project>> {
+    let project = sqlx::query_as::<_, Project>(
+        "UPDATE projects SET deleted_at = NULL
+         WHERE id = $1 AND user_id = $2 AND deleted_at IS NOT NULL
+         RETURNING *",
+    )
+    .bind(project_id)
+    .bind(auth.user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Project {project_id} not found in trash")))?;
+
+    Ok(Json(project))
+}
+
+/// List trashed nodes of a project
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/trash",
+    params(("project_id" = Uuid, Path, description = "Project UUID")),
+    responses(
+        (status = 200, description = "Trashed nodes, most recently deleted first", body = Vec<TrashedNode>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_trashed_nodes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<Vec<TrashedNode>>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let nodes = sqlx::query_as::<_, TrashedNode>(
+        "SELECT client_id, node_type, title, deleted_at FROM canvas_nodes
+         WHERE project_id = $1 AND deleted_at IS NOT NULL
+         ORDER BY deleted_at DESC",
+    )
+    .bind(project_id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(nodes))
+}
+
+/// Restore a trashed node, together with the group it was trashed with
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/nodes/{client_id}/restore",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Frontend-generated node ID"),
+    ),
+    responses(
+        (status = 200, description = "Restored node", body = CanvasNodeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Node not found in trash"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn restore_node(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+) -> Result<Json<CanvasNodeResponse>> {
+    verify_project_owner(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let rows = sqlx::query(
+        "UPDATE canvas_nodes SET deleted_at = NULL
+         WHERE project_id = $1 AND client_id = $2 AND deleted_at IS NOT NULL",
+    )
+    .bind(project_id)
+    .bind(&client_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if rows == 0 {
+        return Err(AppError::NotFound(format!(
+            "Node '{client_id}' not found in trash"
+        )));
+    }
+    groups::restore_groups_of(&mut *tx, project_id, &[client_id.as_str()]).await?;
+    tx.commit().await?;
+
+    let node = fetch_node_response(&state, project_id, &client_id).await?;
+    Ok(Json(node))
+}
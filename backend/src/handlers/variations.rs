@@ -1,14 +1,22 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
 use serde_json::{json, Value};
+use sqlx::{Postgres, QueryBuilder};
 use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
-    middleware::auth::AuthUser,
-    models::ui_variation::{SaveVariationsRequest, UiVariation},
+    handlers::nodes::{fetch_node_response, live_node_id},
+    handlers::{locks, revisions},
+    middleware::{auth::AuthUser, lock_token::LockToken},
+    models::node_revision::RevisionFields,
+    models::ui_variation::{
+        ListVariationsQuery, SaveVariationsRequest, UiVariation, VariationCategory,
+        VariationSelection, VariationSort,
+    },
+    pagination::{list_limit, push_page, Cursor, Page, SortOrder},
     state::AppState,
 };
 
@@ -16,17 +24,46 @@ pub async fn list_variations(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(project_id): Path<Uuid>,
-) -> Result<Json<Vec<UiVariation>>> {
+    Query(params): Query<ListVariationsQuery>,
+) -> Result<Page<UiVariation>> {
     ensure_project_owned(&state, auth.user_id, project_id).await?;
 
-    let variations = sqlx::query_as::<_, UiVariation>(
-        "SELECT * FROM ui_variations WHERE project_id = $1 ORDER BY created_at DESC",
-    )
-    .bind(project_id)
-    .fetch_all(&state.db)
-    .await?;
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or(SortOrder::Desc);
+    let cursor = params.cursor.as_deref().map(Cursor::decode).transpose()?;
+    let limit = list_limit(params.limit, cursor.as_ref());
+
+    let push_filters = |qb: &mut QueryBuilder<'_, Postgres>| {
+        qb.push(" WHERE v.project_id = ").push_bind(project_id).push(
+            " AND NOT EXISTS (
+                 SELECT 1 FROM canvas_nodes n
+                 WHERE n.project_id = v.project_id
+                   AND n.client_id = v.source_node_client_id
+                   AND n.deleted_at IS NOT NULL
+             )",
+        );
+        if let Some(source) = &params.source_node_client_id {
+            qb.push(" AND v.source_node_client_id = ")
+                .push_bind(source.clone());
+        }
+        if let Some(category) = &params.category {
+            qb.push(" AND v.category = ").push_bind(category.clone());
+        }
+    };
 
-    Ok(Json(variations))
+    let mut count_qb = QueryBuilder::new("SELECT COUNT(*) FROM ui_variations v");
+    push_filters(&mut count_qb);
+    let total: i64 = count_qb.build_query_scalar().fetch_one(&state.db).await?;
+
+    let mut qb = QueryBuilder::new("SELECT v.* FROM ui_variations v");
+    push_filters(&mut qb);
+    push_page(&mut qb, sort.column(), "v.id", order, cursor, limit)?;
+    let variations: Vec<UiVariation> = qb.build_query_as().fetch_all(&state.db).await?;
+
+    Ok(Page::from_rows(variations, limit, total, |v| match sort {
+        VariationSort::CreatedAt => Cursor::timestamp(v.created_at, v.id),
+        VariationSort::Label => Cursor::text(&v.label, v.id),
+    }))
 }
 
 pub async fn save_variations(
@@ -37,25 +74,43 @@ pub async fn save_variations(
 ) -> Result<Json<Value>> {
     ensure_project_owned(&state, auth.user_id, project_id).await?;
 
-    for v in &req.variations {
-        sqlx::query(
-            "INSERT INTO ui_variations
-             (project_id, source_node_client_id, label, description, preview_html, code, category)
-             VALUES ($1, $2, $3, $4, $5, $6, $7)",
-        )
-        .bind(project_id)
-        .bind(&req.source_node_client_id)
-        .bind(&v.label)
-        .bind(&v.description)
-        .bind(&v.preview_html)
-        .bind(&v.code)
-        .bind(&v.category)
-        .execute(&state.db)
-        .await?;
-    }
+    let labels: Vec<&str> = req.variations.iter().map(|v| v.label.as_str()).collect();
+    let descriptions: Vec<&str> = req.variations.iter().map(|v| v.description.as_str()).collect();
+    let previews: Vec<&str> = req.variations.iter().map(|v| v.preview_html.as_str()).collect();
+    let codes: Vec<&str> = req.variations.iter().map(|v| v.code.as_str()).collect();
+    let categories: Vec<VariationCategory> =
+        req.variations.iter().map(|v| v.category.clone()).collect();
 
-    let count = req.variations.len();
-    Ok(Json(json!({ "message": "Variations saved", "count": count })))
+    // The whole batch lands or none of it; bodies the node already has, or
+    // that repeat within the batch, are skipped
+    let mut tx = state.db.begin().await?;
+    live_node_id(&mut *tx, project_id, &req.source_node_client_id).await?;
+    let saved = sqlx::query(
+        "INSERT INTO ui_variations
+         (project_id, source_node_client_id, label, description, preview_html, code, category)
+         SELECT $1, $2, * FROM UNNEST(
+             $3::text[], $4::text[], $5::text[], $6::text[], $7::variation_category[]
+         )
+         ON CONFLICT (project_id, source_node_client_id, content_hash) DO NOTHING",
+    )
+    .bind(project_id)
+    .bind(&req.source_node_client_id)
+    .bind(&labels)
+    .bind(&descriptions)
+    .bind(&previews)
+    .bind(&codes)
+    .bind(&categories)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+
+    let duplicates = req.variations.len() as u64 - saved;
+    Ok(Json(json!({
+        "message": "Variations saved",
+        "count": saved,
+        "duplicates": duplicates,
+    })))
 }
 
 pub async fn delete_variation(
@@ -83,13 +138,131 @@ pub async fn delete_variation(
     Ok(Json(json!({ "message": "Variation deleted" })))
 }
 
+/// Delete every variation generated for a node
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{project_id}/nodes/{client_id}/variations",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("client_id" = String, Path, description = "Source node ID"),
+    ),
+    responses(
+        (status = 200, description = "Number of variations deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Project not found"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_node_variations(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((project_id, client_id)): Path<(Uuid, String)>,
+) -> Result<Json<Value>> {
+    ensure_project_owned(&state, auth.user_id, project_id).await?;
+
+    let count = sqlx::query(
+        "DELETE FROM ui_variations WHERE project_id = $1 AND source_node_client_id = $2",
+    )
+    .bind(project_id)
+    .bind(&client_id)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+
+    Ok(Json(json!({ "message": "Variations deleted", "count": count })))
+}
+
+/// Apply a variation to its source node: its preview becomes the node's
+/// content, its code the generated code, and its category the page role.
+/// The node's previous content is kept as a revision.
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/variations/{variation_id}/select",
+    params(
+        ("project_id" = Uuid, Path, description = "Project UUID"),
+        ("variation_id" = Uuid, Path, description = "Variation UUID"),
+        ("X-Lock-Token" = Option<Uuid>, Header, description = "Lock token of the calling session"),
+    ),
+    responses(
+        (status = 200, description = "Selected variation and the updated node", body = VariationSelection),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Variation or its source node not found"),
+        (status = 409, description = "Another session holds the source node's edit lock"),
+    ),
+    security(("bearer_auth" = []))
+)]
+pub async fn select_variation(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    LockToken(lock_token): LockToken,
+    Path((project_id, variation_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<VariationSelection>> {
+    ensure_project_owned(&state, auth.user_id, project_id).await?;
+
+    let mut tx = state.db.begin().await?;
+    let variation = sqlx::query_as::<_, UiVariation>(
+        "SELECT * FROM ui_variations WHERE id = $1 AND project_id = $2 FOR UPDATE",
+    )
+    .bind(variation_id)
+    .bind(project_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound(format!("Variation {variation_id} not found")))?;
+
+    let client_id = &variation.source_node_client_id;
+    let node = revisions::lock_node(&mut tx, project_id, client_id).await?;
+    locks::ensure_unlocked(&mut tx, node.id, client_id, lock_token).await?;
+
+    sqlx::query(
+        "UPDATE ui_variations SET selected_at = NULL, selected_by = NULL
+         WHERE project_id = $1 AND source_node_client_id = $2
+           AND selected_at IS NOT NULL AND id <> $3",
+    )
+    .bind(project_id)
+    .bind(client_id)
+    .bind(variation_id)
+    .execute(&mut *tx)
+    .await?;
+    let variation = sqlx::query_as::<_, UiVariation>(
+        "UPDATE ui_variations SET selected_at = NOW(), selected_by = $2
+         WHERE id = $1
+         RETURNING *",
+    )
+    .bind(variation_id)
+    .bind(auth.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE canvas_nodes
+         SET content = $2, generated_code = $3, page_role = COALESCE($4, page_role)
+         WHERE id = $1",
+    )
+    .bind(node.id)
+    .bind(&variation.preview_html)
+    .bind(&variation.code)
+    .bind(variation.category.page_role())
+    .execute(&mut *tx)
+    .await?;
+    let after = RevisionFields {
+        content: Some(variation.preview_html.clone()),
+        generated_code: Some(variation.code.clone()),
+        ..node.fields.clone()
+    };
+    revisions::record_revision(&mut tx, &node, &after, auth.user_id).await?;
+    tx.commit().await?;
+
+    let node = fetch_node_response(&state, project_id, &variation.source_node_client_id).await?;
+    Ok(Json(VariationSelection { variation, node }))
+}
+
 async fn ensure_project_owned(
     state: &AppState,
     user_id: Uuid,
     project_id: Uuid,
 ) -> Result<()> {
     let exists = sqlx::query_scalar::<_, i64>(
-        "SELECT COUNT(*) FROM projects WHERE id = $1 AND user_id = $2",
+        "SELECT COUNT(*) FROM projects WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL",
     )
     .bind(project_id)
     .bind(user_id)
@@ -0,0 +1,12 @@
+pub mod seal_secrets;
+pub mod status_sweeper;
+pub mod trash_purge;
+
+use crate::state::AppState;
+
+/// Spawn all long-running background tasks
+pub fn spawn_all(state: &AppState) {
+    tokio::spawn(trash_purge::run(state.clone()));
+    tokio::spawn(status_sweeper::run(state.clone()));
+    tokio::spawn(seal_secrets::run(state.clone()));
+}
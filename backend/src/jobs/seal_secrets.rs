@@ -0,0 +1,101 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{error::Result, secrets, state::AppState};
+
+/// Matches config text holding a non-empty secret field that is not sealed
+const PLAINTEXT_CODE: &str = r#"'"(secretKey|webhookSecret)"\s*:\s*"(?!enc:v1:|")'"#;
+
+/// Encrypt payment secrets written before they were sealed on save. Runs once
+/// at startup; a no-op once every row is sealed.
+pub async fn run(state: AppState) {
+    match seal_plaintext(&state).await {
+        Ok(0) => {}
+        Ok(sealed) => tracing::info!("Encrypted plaintext payment secrets in {sealed} rows"),
+        Err(e) => tracing::error!("Sealing stored payment secrets failed: {e:?}"),
+    }
+}
+
+async fn seal_plaintext(state: &AppState) -> Result<u64> {
+    let secret = &state.cfg.api_key_encryption_secret;
+    let mut tx = state.db.begin().await?;
+
+    let nodes = sqlx::query_as::<_, (Uuid, Option<Value>, Option<String>)>(&format!(
+        "SELECT id, payload, generated_code FROM canvas_nodes
+         WHERE node_type = 'payment'
+           AND ((payload ->> 'secretKey') !~ '^(enc:v1:|$)'
+             OR (payload ->> 'webhookSecret') !~ '^(enc:v1:|$)'
+             OR generated_code ~ {PLAINTEXT_CODE})
+         FOR UPDATE"
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+    let revisions = sqlx::query_as::<_, (Uuid, Option<Value>, Option<String>)>(&format!(
+        "SELECT r.id, NULL::jsonb AS payload, r.generated_code
+         FROM node_revisions r
+         JOIN canvas_nodes n ON n.id = r.node_id
+         WHERE n.node_type = 'payment' AND r.generated_code ~ {PLAINTEXT_CODE}
+         FOR UPDATE OF r"
+    ))
+    .fetch_all(&mut *tx)
+    .await?;
+    if nodes.is_empty() && revisions.is_empty() {
+        return Ok(0);
+    }
+
+    let (node_ids, payloads, codes) = seal_rows(nodes, secret)?;
+    let (revision_ids, _, revision_codes) = seal_rows(revisions, secret)?;
+
+    // Sealing is not an edit; keep updated_at, which orders nodes and env layers
+    sqlx::query("ALTER TABLE canvas_nodes DISABLE TRIGGER set_canvas_nodes_timestamp")
+        .execute(&mut *tx)
+        .await?;
+    let mut sealed = sqlx::query(
+        "UPDATE canvas_nodes n SET payload = u.payload, generated_code = u.code
+         FROM UNNEST($1::uuid[], $2::jsonb[], $3::text[]) AS u(id, payload, code)
+         WHERE n.id = u.id",
+    )
+    .bind(&node_ids)
+    .bind(&payloads)
+    .bind(&codes)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    sqlx::query("ALTER TABLE canvas_nodes ENABLE TRIGGER set_canvas_nodes_timestamp")
+        .execute(&mut *tx)
+        .await?;
+
+    sealed += sqlx::query(
+        "UPDATE node_revisions r SET generated_code = u.code
+         FROM UNNEST($1::uuid[], $2::text[]) AS u(id, code)
+         WHERE r.id = u.id",
+    )
+    .bind(&revision_ids)
+    .bind(&revision_codes)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok(sealed)
+}
+
+type SealedRows = (Vec<Uuid>, Vec<Option<Value>>, Vec<Option<String>>);
+
+fn seal_rows(rows: Vec<(Uuid, Option<Value>, Option<String>)>, secret: &str) -> Result<SealedRows> {
+    let mut sealed: SealedRows = Default::default();
+    for (id, mut payload, mut code) in rows {
+        if let Some(config) = payload.as_mut() {
+            secrets::seal_stored_payment(config, secret)?;
+        }
+        if let Some(text) = code.as_deref() {
+            if let Some(text) = secrets::seal_stored_payment_code(text, secret)? {
+                code = Some(text);
+            }
+        }
+        sealed.0.push(id);
+        sealed.1.push(payload);
+        sealed.2.push(code);
+    }
+    Ok(sealed)
+}
@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use crate::{error::Result, state::AppState};
+
+/// Periodically reset nodes whose generation or run was abandoned, e.g. when
+/// the browser tab driving it closed
+pub async fn run(state: AppState) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(state.cfg.status_sweep_interval_secs));
+
+    loop {
+        interval.tick().await;
+        match reset_stuck(&state).await {
+            Ok(0) => {}
+            Ok(reset) => tracing::info!("Reset {reset} nodes stuck generating or running"),
+            Err(e) => tracing::error!("Status sweep failed: {e:?}"),
+        }
+    }
+}
+
+/// Generating nodes fall back to idle and running nodes to ready, both
+/// transitions the lifecycle allows
+async fn reset_stuck(state: &AppState) -> Result<u64> {
+    let reset = sqlx::query(
+        "UPDATE canvas_nodes SET status = CASE status
+                                     WHEN 'generating' THEN 'idle'::node_status
+                                     ELSE 'ready'::node_status
+                                 END
+         WHERE deleted_at IS NULL
+           AND ((status = 'generating'
+                 AND status_changed_at < NOW() - make_interval(secs => $1))
+             OR (status = 'running'
+                 AND status_changed_at < NOW() - make_interval(secs => $2)))",
+    )
+    .bind(state.cfg.node_generating_timeout_secs as f64)
+    .bind(state.cfg.node_running_timeout_secs as f64)
+    .execute(&state.db)
+    .await?
+    .rows_affected();
+
+    Ok(reset)
+}
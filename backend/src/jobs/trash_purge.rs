@@ -0,0 +1,78 @@
+use chrono::{Duration as ChronoDuration, Utc};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{
+    error::Result,
+    handlers::{assets, nodes},
+    state::AppState,
+};
+
+/// Periodically hard-delete projects, nodes and groups that have sat in the trash
+/// longer than the configured retention period
+pub async fn run(state: AppState) {
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(state.cfg.trash_purge_interval_secs));
+
+    loop {
+        interval.tick().await;
+        match purge_expired(&state).await {
+            Ok((projects, nodes)) if projects + nodes > 0 => {
+                tracing::info!("Purged {projects} trashed projects and {nodes} trashed nodes");
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Trash purge failed: {e:?}"),
+        }
+    }
+}
+
+async fn purge_expired(state: &AppState) -> Result<(u64, u64)> {
+    let cutoff = Utc::now() - ChronoDuration::days(state.cfg.trash_retention_days);
+
+    let mut tx = state.db.begin().await?;
+
+    // Asset rows go with their project through the cascade; the objects they
+    // point at are shared by content hash and are removed once unreferenced
+    let mut hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT a.sha256 FROM assets a
+         JOIN projects p ON p.id = a.project_id
+         WHERE p.deleted_at < $1",
+    )
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+    assets::lock_blobs(&mut tx, &mut hashes).await?;
+
+    let projects = sqlx::query("DELETE FROM projects WHERE deleted_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    assets::delete_unreferenced_blobs(state, &mut tx, &hashes).await?;
+
+    // Connections go with the node through their foreign keys; element links
+    // are JSON and have to be stripped from the surviving nodes by hand.
+    let purged_projects: Vec<Uuid> = sqlx::query_scalar(
+        "DELETE FROM canvas_nodes WHERE deleted_at < $1 RETURNING project_id",
+    )
+    .bind(cutoff)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let nodes = purged_projects.len() as u64;
+    let mut affected = purged_projects;
+    affected.sort();
+    affected.dedup();
+    nodes::strip_dangling_element_links(&mut tx, &affected).await?;
+
+    // Groups trashed with their contents expire along with them
+    sqlx::query("DELETE FROM canvas_groups WHERE deleted_at < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok((projects, nodes))
+}
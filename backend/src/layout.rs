@@ -0,0 +1,566 @@
+//! Automatic canvas layouts. Every algorithm returns top-left positions for
+//! each input node such that no two node rectangles overlap.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A node to be placed
+#[derive(Debug, Clone)]
+pub struct LayoutNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Gaps between nodes and where the layout starts
+#[derive(Debug, Clone, Copy)]
+pub struct Spacing {
+    /// Horizontal gap between neighbours in a layer, row or sibling group
+    pub node: f64,
+    /// Vertical gap between layers, tree levels or grid rows
+    pub layer: f64,
+    pub origin_x: f64,
+    pub origin_y: f64,
+}
+
+pub type Positions = HashMap<String, (f64, f64)>;
+
+/// A canvas area a layout has to stay clear of, such as an unselected node
+#[derive(Debug, Clone, Copy)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Number of barycenter sweeps used to reduce edge crossings
+const ORDERING_SWEEPS: usize = 4;
+
+/// Layered (Sugiyama-style) layout of a directed graph: cycles are broken,
+/// nodes are ranked by longest path from the sources, each layer is ordered
+/// by the barycenter of its neighbours, and layers are stacked top to bottom.
+pub fn layered(nodes: &[LayoutNode], edges: &[(String, String)], spacing: Spacing) -> Positions {
+    let index: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let mut succ: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (from, to) in edges {
+        if let (Some(&f), Some(&t)) = (index.get(from.as_str()), index.get(to.as_str())) {
+            if f != t && !succ[f].contains(&t) {
+                succ[f].push(t);
+            }
+        }
+    }
+    let succ = remove_cycles(succ);
+
+    // Longest-path ranking in topological order
+    let mut indegree = vec![0usize; nodes.len()];
+    for targets in &succ {
+        for &t in targets {
+            indegree[t] += 1;
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..nodes.len()).filter(|&i| indegree[i] == 0).collect();
+    let mut rank = vec![0usize; nodes.len()];
+    while let Some(n) = queue.pop_front() {
+        for &t in &succ[n] {
+            rank[t] = rank[t].max(rank[n] + 1);
+            indegree[t] -= 1;
+            if indegree[t] == 0 {
+                queue.push_back(t);
+            }
+        }
+    }
+
+    let depth = rank.iter().copied().max().map_or(0, |r| r + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); depth];
+    for (i, &r) in rank.iter().enumerate() {
+        layers[r].push(i);
+    }
+
+    let mut pred: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (from, targets) in succ.iter().enumerate() {
+        for &t in targets {
+            pred[t].push(from);
+        }
+    }
+
+    for sweep in 0..ORDERING_SWEEPS {
+        if sweep % 2 == 0 {
+            for l in 1..layers.len() {
+                let (above, rest) = layers.split_at_mut(l);
+                order_by_barycenter(&mut rest[0], &above[l - 1], &pred);
+            }
+        } else {
+            for l in (0..layers.len().saturating_sub(1)).rev() {
+                let (head, below) = layers.split_at_mut(l + 1);
+                order_by_barycenter(&mut head[l], &below[0], &succ);
+            }
+        }
+    }
+
+    stack_rows(nodes, &layers, spacing)
+}
+
+/// Tree layout following `parent_id`. Nodes whose parent is not part of the
+/// layout become roots; each subtree gets its own horizontal band so
+/// siblings never overlap, and parents are centred over their children.
+pub fn tree(nodes: &[LayoutNode], spacing: Spacing) -> Positions {
+    let index: HashMap<&str, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.as_str(), i))
+        .collect();
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut roots = Vec::new();
+    for (i, node) in nodes.iter().enumerate() {
+        match node.parent_id.as_deref().and_then(|p| index.get(p)) {
+            Some(&p) if p != i => children[p].push(i),
+            _ => roots.push(i),
+        }
+    }
+
+    // A parent_id cycle leaves nodes unreachable from any root; promote one
+    // node of each such cycle to a root and cut its incoming edge.
+    let mut reached = HashSet::new();
+    let mut stack = roots.clone();
+    loop {
+        while let Some(n) = stack.pop() {
+            if reached.insert(n) {
+                stack.extend(children[n].iter().copied());
+            }
+        }
+        match (0..nodes.len()).find(|i| !reached.contains(i)) {
+            Some(orphan) => {
+                for list in children.iter_mut() {
+                    list.retain(|&c| c != orphan);
+                }
+                roots.push(orphan);
+                stack.push(orphan);
+            }
+            None => break,
+        }
+    }
+
+    // Height of each depth so levels never overlap vertically
+    let mut level_height: Vec<f64> = Vec::new();
+    let mut depth_of = vec![0usize; nodes.len()];
+    let mut queue: VecDeque<usize> = roots.iter().copied().collect();
+    while let Some(n) = queue.pop_front() {
+        let d = depth_of[n];
+        if level_height.len() <= d {
+            level_height.resize(d + 1, 0.0);
+        }
+        level_height[d] = level_height[d].max(nodes[n].height);
+        for &c in &children[n] {
+            depth_of[c] = d + 1;
+            queue.push_back(c);
+        }
+    }
+    let mut level_y = Vec::with_capacity(level_height.len());
+    let mut y = spacing.origin_y;
+    for h in &level_height {
+        level_y.push(y);
+        y += h + spacing.layer;
+    }
+
+    let mut band = vec![0.0; nodes.len()];
+    for &r in roots.iter().rev() {
+        subtree_width(r, nodes, &children, spacing.node, &mut band);
+    }
+
+    let mut positions = Positions::new();
+    let mut x = spacing.origin_x;
+    for &r in &roots {
+        place_subtree(
+            r,
+            x,
+            nodes,
+            &children,
+            &band,
+            &depth_of,
+            &level_y,
+            spacing,
+            &mut positions,
+        );
+        x += band[r] + spacing.node;
+    }
+    positions
+}
+
+/// Grid layout in input order, as close to square as possible. Columns are as
+/// wide as their widest node and rows as tall as their tallest.
+pub fn grid(nodes: &[LayoutNode], spacing: Spacing) -> Positions {
+    if nodes.is_empty() {
+        return Positions::new();
+    }
+    let cols = (nodes.len() as f64).sqrt().ceil() as usize;
+    let rows = nodes.len().div_ceil(cols);
+
+    let mut col_width = vec![0.0f64; cols];
+    let mut row_height = vec![0.0f64; rows];
+    for (i, node) in nodes.iter().enumerate() {
+        col_width[i % cols] = col_width[i % cols].max(node.width);
+        row_height[i / cols] = row_height[i / cols].max(node.height);
+    }
+
+    let col_x = offsets(&col_width, spacing.origin_x, spacing.node);
+    let row_y = offsets(&row_height, spacing.origin_y, spacing.layer);
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(i, n)| (n.id.clone(), (col_x[i % cols], row_y[i / cols])))
+        .collect()
+}
+
+/// Move a finished layout as one block out of the way of `obstacles`. The
+/// block keeps its place when its bounding box is already clear; otherwise it
+/// takes the shortest shift right or down that clears them, landing `gap`
+/// past the obstacle it steps over.
+pub fn avoid(positions: &mut Positions, nodes: &[LayoutNode], obstacles: &[Rect], gap: f64) {
+    let placed: Vec<Rect> = nodes
+        .iter()
+        .filter_map(|n| {
+            positions.get(&n.id).map(|&(x, y)| Rect {
+                x,
+                y,
+                width: n.width,
+                height: n.height,
+            })
+        })
+        .collect();
+    let Some(left) = placed.iter().map(|r| r.x).reduce(f64::min) else {
+        return;
+    };
+    let top = placed.iter().map(|r| r.y).fold(f64::INFINITY, f64::min);
+    let right = placed.iter().map(|r| r.x + r.width).fold(f64::NEG_INFINITY, f64::max);
+    let bottom = placed.iter().map(|r| r.y + r.height).fold(f64::NEG_INFINITY, f64::max);
+    let block = Rect {
+        x: left,
+        y: top,
+        width: right - left,
+        height: bottom - top,
+    };
+
+    let clear = |(dx, dy): (f64, f64)| {
+        let moved = Rect {
+            x: block.x + dx,
+            y: block.y + dy,
+            ..block
+        };
+        !obstacles.iter().any(|o| o.overlaps(&moved))
+    };
+    if clear((0.0, 0.0)) {
+        return;
+    }
+
+    // Shifting below the lowest obstacle always clears; every other
+    // candidate lines the block up just past one obstacle's edge
+    let mut shifts: Vec<(f64, f64)> = obstacles
+        .iter()
+        .flat_map(|o| {
+            [
+                (o.x + o.width + gap - block.x, 0.0),
+                (0.0, o.y + o.height + gap - block.y),
+            ]
+        })
+        .filter(|&(dx, dy)| dx > 0.0 || dy > 0.0)
+        .collect();
+    shifts.sort_by(|a, b| (a.0 + a.1).total_cmp(&(b.0 + b.1)));
+    let fallback = shifts
+        .iter()
+        .copied()
+        .filter(|s| s.0 == 0.0)
+        .fold((0.0, 0.0), |a, b| if b.1 > a.1 { b } else { a });
+    let (dx, dy) = shifts.into_iter().find(|&s| clear(s)).unwrap_or(fallback);
+
+    for (x, y) in positions.values_mut() {
+        *x += dx;
+        *y += dy;
+    }
+}
+
+/// Reverse edges that close a cycle, found by depth-first search
+fn remove_cycles(succ: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        New,
+        Active,
+        Done,
+    }
+
+    let mut mark = vec![Mark::New; succ.len()];
+    let mut acyclic: Vec<Vec<usize>> = vec![Vec::new(); succ.len()];
+    for start in 0..succ.len() {
+        if mark[start] != Mark::New {
+            continue;
+        }
+        let mut stack = vec![(start, 0usize)];
+        mark[start] = Mark::Active;
+        while let Some((n, next)) = stack.pop() {
+            match succ[n].get(next) {
+                Some(&t) => {
+                    stack.push((n, next + 1));
+                    match mark[t] {
+                        Mark::Active => acyclic[t].push(n),
+                        Mark::Done => acyclic[n].push(t),
+                        Mark::New => {
+                            acyclic[n].push(t);
+                            mark[t] = Mark::Active;
+                            stack.push((t, 0));
+                        }
+                    }
+                }
+                None => mark[n] = Mark::Done,
+            }
+        }
+    }
+    for list in acyclic.iter_mut() {
+        list.sort_unstable();
+        list.dedup();
+    }
+    acyclic
+}
+
+/// Sort a layer by the mean position of each node's neighbours in the
+/// adjacent layer; nodes without neighbours keep their current position
+fn order_by_barycenter(layer: &mut [usize], adjacent: &[usize], neighbours: &[Vec<usize>]) {
+    let pos: HashMap<usize, usize> = adjacent.iter().enumerate().map(|(i, &n)| (n, i)).collect();
+    let mut keyed: Vec<(f64, usize)> = layer
+        .iter()
+        .enumerate()
+        .map(|(i, &n)| {
+            let linked: Vec<f64> = neighbours[n]
+                .iter()
+                .filter_map(|m| pos.get(m).map(|&p| p as f64))
+                .collect();
+            let key = if linked.is_empty() {
+                i as f64
+            } else {
+                linked.iter().sum::<f64>() / linked.len() as f64
+            };
+            (key, n)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (slot, (_, n)) in layer.iter_mut().zip(keyed) {
+        *slot = n;
+    }
+}
+
+/// Place layers as horizontally centred rows stacked top to bottom
+fn stack_rows(nodes: &[LayoutNode], layers: &[Vec<usize>], spacing: Spacing) -> Positions {
+    let row_width = |layer: &Vec<usize>| {
+        layer.iter().map(|&n| nodes[n].width).sum::<f64>()
+            + spacing.node * layer.len().saturating_sub(1) as f64
+    };
+    let widest = layers.iter().map(row_width).fold(0.0, f64::max);
+
+    let mut positions = Positions::new();
+    let mut y = spacing.origin_y;
+    for layer in layers {
+        let mut x = spacing.origin_x + (widest - row_width(layer)) / 2.0;
+        let mut height = 0.0f64;
+        for &n in layer {
+            positions.insert(nodes[n].id.clone(), (x, y));
+            x += nodes[n].width + spacing.node;
+            height = height.max(nodes[n].height);
+        }
+        y += height + spacing.layer;
+    }
+    positions
+}
+
+fn subtree_width(
+    n: usize,
+    nodes: &[LayoutNode],
+    children: &[Vec<usize>],
+    gap: f64,
+    band: &mut [f64],
+) -> f64 {
+    let kids: f64 = children[n]
+        .iter()
+        .map(|&c| subtree_width(c, nodes, children, gap, band))
+        .sum::<f64>()
+        + gap * children[n].len().saturating_sub(1) as f64;
+    band[n] = nodes[n].width.max(kids);
+    band[n]
+}
+
+#[allow(clippy::too_many_arguments)]
+fn place_subtree(
+    n: usize,
+    left: f64,
+    nodes: &[LayoutNode],
+    children: &[Vec<usize>],
+    band: &[f64],
+    depth_of: &[usize],
+    level_y: &[f64],
+    spacing: Spacing,
+    positions: &mut Positions,
+) {
+    let x = left + (band[n] - nodes[n].width) / 2.0;
+    positions.insert(nodes[n].id.clone(), (x, level_y[depth_of[n]]));
+
+    let kids: f64 = children[n].iter().map(|&c| band[c]).sum::<f64>()
+        + spacing.node * children[n].len().saturating_sub(1) as f64;
+    let mut child_left = left + (band[n] - kids) / 2.0;
+    for &c in &children[n] {
+        place_subtree(
+            c, child_left, nodes, children, band, depth_of, level_y, spacing, positions,
+        );
+        child_left += band[c] + spacing.node;
+    }
+}
+
+/// Start offsets of consecutive cells of the given sizes
+fn offsets(sizes: &[f64], origin: f64, gap: f64) -> Vec<f64> {
+    let mut at = origin;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = at;
+            at += size + gap;
+            start
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, parent: Option<&str>, width: f64, height: f64) -> LayoutNode {
+        LayoutNode {
+            id: id.into(),
+            parent_id: parent.map(Into::into),
+            width,
+            height,
+        }
+    }
+
+    fn assert_no_overlap(nodes: &[LayoutNode], positions: &Positions) {
+        assert_eq!(positions.len(), nodes.len());
+        for (i, a) in nodes.iter().enumerate() {
+            for b in &nodes[i + 1..] {
+                let (ax, ay) = positions[&a.id];
+                let (bx, by) = positions[&b.id];
+                let apart = ax + a.width <= bx
+                    || bx + b.width <= ax
+                    || ay + a.height <= by
+                    || by + b.height <= ay;
+                assert!(apart, "{} overlaps {}", a.id, b.id);
+            }
+        }
+    }
+
+    const SPACING: Spacing = Spacing {
+        node: 10.0,
+        layer: 20.0,
+        origin_x: 0.0,
+        origin_y: 0.0,
+    };
+
+    #[test]
+    fn layouts_never_overlap() {
+        let nodes: Vec<LayoutNode> = (0..12)
+            .map(|i| {
+                let parent = (i > 0).then(|| format!("n{}", (i - 1) / 3));
+                node(
+                    &format!("n{i}"),
+                    parent.as_deref(),
+                    50.0 + i as f64 * 7.0,
+                    30.0 + i as f64,
+                )
+            })
+            .collect();
+        let mut edges: Vec<(String, String)> = nodes
+            .iter()
+            .filter_map(|n| n.parent_id.clone().map(|p| (p, n.id.clone())))
+            .collect();
+        edges.push(("n11".into(), "n0".into()));
+
+        assert_no_overlap(&nodes, &layered(&nodes, &edges, SPACING));
+        assert_no_overlap(&nodes, &tree(&nodes, SPACING));
+        assert_no_overlap(&nodes, &grid(&nodes, SPACING));
+    }
+
+    #[test]
+    fn layered_ranks_follow_edges_and_tree_survives_parent_cycles() {
+        let nodes = vec![
+            node("a", Some("c"), 40.0, 40.0),
+            node("b", Some("a"), 40.0, 40.0),
+            node("c", Some("b"), 40.0, 40.0),
+        ];
+        let edges = vec![("a".into(), "b".into()), ("b".into(), "c".into())];
+
+        let positions = layered(&nodes, &edges, SPACING);
+        assert!(positions["a"].1 < positions["b"].1);
+        assert!(positions["b"].1 < positions["c"].1);
+
+        assert_no_overlap(&nodes, &tree(&nodes, SPACING));
+    }
+
+    #[test]
+    fn avoid_moves_the_block_clear_of_obstacles() {
+        let nodes: Vec<LayoutNode> = (0..4)
+            .map(|i| node(&format!("n{i}"), None, 50.0, 30.0))
+            .collect();
+        let mut positions = grid(&nodes, SPACING);
+        let untouched = positions.clone();
+
+        let far = [Rect {
+            x: 500.0,
+            y: 500.0,
+            width: 50.0,
+            height: 50.0,
+        }];
+        avoid(&mut positions, &nodes, &far, SPACING.node);
+        assert_eq!(positions, untouched);
+
+        let obstacles = [
+            Rect {
+                x: 20.0,
+                y: 20.0,
+                width: 200.0,
+                height: 40.0,
+            },
+            Rect {
+                x: 0.0,
+                y: 100.0,
+                width: 40.0,
+                height: 300.0,
+            },
+        ];
+        avoid(&mut positions, &nodes, &obstacles, SPACING.node);
+        for n in &nodes {
+            let (x, y) = positions[&n.id];
+            let rect = Rect {
+                x,
+                y,
+                width: n.width,
+                height: n.height,
+            };
+            assert!(obstacles.iter().all(|o| !o.overlaps(&rect)), "{} overlaps", n.id);
+        }
+        // The layout itself is only translated
+        let dx = positions["n0"].0 - untouched["n0"].0;
+        let dy = positions["n0"].1 - untouched["n0"].1;
+        assert!(dx > 0.0 || dy > 0.0);
+        for n in &nodes {
+            assert_eq!(positions[&n.id], (untouched[&n.id].0 + dx, untouched[&n.id].1 + dy));
+        }
+    }
+}
@@ -6,12 +6,23 @@ use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod config;
+mod dotenv;
+mod element_links;
+mod environments;
 mod error;
+mod graph;
 mod handlers;
+mod jobs;
+mod layout;
 mod middleware;
 mod models;
+mod pagination;
 mod routes;
+mod secrets;
 mod state;
+mod storage;
+#[cfg(test)]
+mod test_support;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -36,11 +47,13 @@ async fn main() -> anyhow::Result<()> {
     sqlx::migrate!("./migrations").run(&pool).await?;
 
     let app_state = state::AppState::new(pool, cfg.clone());
+    jobs::spawn_all(&app_state);
 
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        .expose_headers(Any);
 
     let app = routes::build_router(app_state)
         .layer(TraceLayer::new_for_http())
@@ -0,0 +1,30 @@
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+/// Header carrying the token an editing session got with its first lock
+pub const LOCK_TOKEN_HEADER: &str = "x-lock-token";
+
+/// The caller's lock token, if it sent one. Locks belong to the editing
+/// session that took them, not to the user: the same owner in another tab or
+/// device gets no token back and cannot write to the locked nodes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LockToken(pub Option<Uuid>);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for LockToken {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(LOCK_TOKEN_HEADER) else {
+            return Ok(LockToken(None));
+        };
+        value
+            .to_str()
+            .ok()
+            .and_then(|v| v.trim().parse::<Uuid>().ok())
+            .map(|token| LockToken(Some(token)))
+            .ok_or_else(|| AppError::Validation("X-Lock-Token must be a UUID".into()))
+    }
+}
@@ -1 +1,2 @@
 pub mod auth;
+pub mod lock_token;
@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// An uploaded file belonging to a project
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    pub id: Uuid,
+    /// Hex SHA-256 of the bytes; uploading the same file again returns this asset
+    pub sha256: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// Pixel dimensions, for raster images
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    /// Whether `/thumbnail` serves a PNG preview
+    pub has_thumbnail: bool,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Multipart body of an asset upload
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AssetUpload {
+    /// PNG, JPEG, GIF, WebP, SVG or PDF
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
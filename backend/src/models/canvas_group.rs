@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CanvasGroup {
+    pub id: Uuid,
+    pub parent_id: Option<Uuid>,
+    pub title: String,
+    pub color: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub collapsed: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A frame on the canvas that contains nodes and other groups
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasGroupResponse {
+    pub id: Uuid,
+    /// Enclosing group, if nested
+    pub parent_id: Option<Uuid>,
+    pub title: String,
+    pub color: String,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub collapsed: bool,
+    /// client_ids of the live nodes directly inside this group
+    pub node_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create a group
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateGroupRequest {
+    pub title: String,
+    pub color: Option<String>,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Group to nest this one inside
+    pub parent_id: Option<Uuid>,
+    /// client_ids of nodes to move into the group
+    pub node_ids: Option<Vec<String>>,
+}
+
+/// Partially update a group
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateGroupRequest {
+    pub title: Option<String>,
+    pub color: Option<String>,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub collapsed: Option<bool>,
+    /// New enclosing group; must not be the group itself or one of its descendants
+    pub parent_id: Option<Uuid>,
+    /// Move the group to the top level
+    pub detach: Option<bool>,
+}
+
+/// Translate a group, its nested groups and every node inside them
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveGroupRequest {
+    pub dx: f64,
+    pub dy: f64,
+}
+
+/// Replace the set of nodes directly inside a group
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetGroupMembersRequest {
+    pub node_ids: Vec<String>,
+}
+
+/// How to treat a group's contents when deleting it
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DeleteGroupQuery {
+    /// Move the group, its nested groups and all their member nodes to the
+    /// trash. Otherwise contents move up to the parent group.
+    pub with_contents: Option<bool>,
+}
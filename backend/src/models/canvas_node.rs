@@ -1,10 +1,15 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::FromRow;
 use std::collections::HashMap;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::models::canvas_group::CanvasGroupResponse;
+use crate::models::integrity::BrokenLinkReason;
+use crate::pagination::{SortColumn, SortOrder};
+
 /// Node type determines what kind of content and editor the node uses
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "node_type", rename_all = "snake_case")]
@@ -22,7 +27,7 @@ pub enum NodeType {
 }
 
 /// Lifecycle status of a node
-#[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "node_status", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum NodeStatus {
@@ -32,6 +37,36 @@ pub enum NodeStatus {
     Running,
 }
 
+impl NodeStatus {
+    /// Whether a node may move from `self` to `next`. Generation can be
+    /// abandoned back to idle, only ready output can be run, and a stopped
+    /// run returns to ready; staying put is always allowed.
+    pub fn can_become(&self, next: &NodeStatus) -> bool {
+        use NodeStatus::*;
+        self == next
+            || matches!(
+                (self, next),
+                (Idle, Generating)
+                    | (Generating, Ready)
+                    | (Generating, Idle)
+                    | (Ready, Generating)
+                    | (Ready, Running)
+                    | (Ready, Idle)
+                    | (Running, Ready)
+                    | (Running, Idle)
+            )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeStatus::Idle => "idle",
+            NodeStatus::Generating => "generating",
+            NodeStatus::Ready => "ready",
+            NodeStatus::Running => "running",
+        }
+    }
+}
+
 /// Target platform for generated output
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "node_platform", rename_all = "snake_case")]
@@ -46,6 +81,25 @@ pub enum NodePlatform {
     Env,
 }
 
+// Enum arrays are bound when inserting whole canvases through UNNEST
+impl PgHasArrayType for NodeType {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_node_type")
+    }
+}
+
+impl PgHasArrayType for NodeStatus {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_node_status")
+    }
+}
+
+impl PgHasArrayType for NodePlatform {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_node_platform")
+    }
+}
+
 /// A link from an HTML element inside a design node to another canvas node
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -58,6 +112,11 @@ pub struct ElementLink {
     pub target_node_id: String,
     /// Element type hint: form, button, input, link, image, etc.
     pub element_type: Option<String>,
+    /// Set by the server when the selector did not resolve in the node's HTML
+    /// as last saved, or the target node does not exist; the link is kept so
+    /// it can be repaired
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub problem: Option<BrokenLinkReason>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
@@ -73,8 +132,12 @@ pub struct CanvasNode {
     pub width: f64,
     pub height: f64,
     pub status: NodeStatus,
+    pub status_changed_at: DateTime<Utc>,
+    /// Absent when the row was loaded without heavy bodies
+    #[sqlx(default)]
     pub content: Option<String>,
     pub file_name: Option<String>,
+    #[sqlx(default)]
     pub generated_code: Option<String>,
     pub picked: bool,
     pub parent_id: Option<String>,
@@ -85,8 +148,17 @@ pub struct CanvasNode {
     pub ai_model: Option<String>,
     pub element_links: serde_json::Value,
     pub env_vars: serde_json::Value,
+    /// Structured editor data, validated against the node type's schema
+    pub payload: Option<serde_json::Value>,
+    /// Group (frame) the node sits in
+    pub group_id: Option<Uuid>,
+    /// JSON array of asset ids
+    pub asset_ids: serde_json::Value,
+    /// md5 over `content` and `generated_code`
+    pub content_hash: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 /// Create a new canvas node
@@ -122,6 +194,13 @@ pub struct CreateNodeRequest {
     pub ai_model: Option<String>,
     pub element_links: Option<Vec<ElementLink>>,
     pub env_vars: Option<HashMap<String, String>>,
+    /// Env var names whose values are secret; stored encrypted and masked on read
+    pub secret_env_vars: Option<Vec<String>>,
+    /// Structured editor data for api, cli, database, payment and env nodes
+    #[schema(value_type = Option<NodePayload>)]
+    pub payload: Option<serde_json::Value>,
+    /// Ids of uploaded assets of this project that the node uses
+    pub asset_ids: Option<Vec<Uuid>>,
     /// client_ids of nodes to connect to
     pub connected_to: Option<Vec<String>>,
 }
@@ -148,16 +227,87 @@ pub struct UpdateNodeRequest {
     pub ai_model: Option<String>,
     pub element_links: Option<Vec<ElementLink>>,
     pub env_vars: Option<HashMap<String, String>>,
+    /// Replaces the set of secret env vars; when omitted, secret vars stay secret.
+    /// A masked value keeps what is stored.
+    pub secret_env_vars: Option<Vec<String>>,
+    /// Replaces the whole payload; validated against the node's type
+    #[schema(value_type = Option<NodePayload>)]
+    pub payload: Option<serde_json::Value>,
+    /// Replaces the node's asset references
+    pub asset_ids: Option<Vec<Uuid>>,
 }
 
-/// Create a directed connection between two nodes
-#[derive(Debug, Deserialize, ToSchema)]
+/// What a connection means
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "connection_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionKind {
+    /// Output of one node feeds the other (e.g. an API reading a database)
+    #[default]
+    DataFlow,
+    /// One page or screen navigates to another
+    Navigation,
+    /// One node must be built before the other
+    DependsOn,
+    /// A node consumes variables from an env node
+    EnvBinding,
+}
+
+impl PgHasArrayType for ConnectionKind {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_connection_kind")
+    }
+}
+
+/// A directed connection with its metadata
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Connection {
+    pub id: Uuid,
+    pub from_client_id: String,
+    pub to_client_id: String,
+    pub kind: ConnectionKind,
+    pub label: Option<String>,
+    /// Named handle on the source node the connection leaves from
+    pub source_port: Option<String>,
+    /// Named handle on the target node the connection arrives at
+    pub target_port: Option<String>,
+    /// Free-form rendering hints such as color, dashed or animated
+    #[schema(value_type = Object)]
+    pub style: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Create a directed connection between two nodes. Connecting an already
+/// connected pair updates the metadata given and keeps the rest.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ConnectNodesRequest {
     /// client_id of the source node
     pub from_client_id: String,
     /// client_id of the target node
     pub to_client_id: String,
+    /// Defaults to data_flow for new connections
+    pub kind: Option<ConnectionKind>,
+    pub label: Option<String>,
+    pub source_port: Option<String>,
+    pub target_port: Option<String>,
+    /// JSON object of rendering hints
+    #[schema(value_type = Option<Object>)]
+    pub style: Option<serde_json::Value>,
+}
+
+/// Update a connection's metadata
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateConnectionRequest {
+    pub kind: Option<ConnectionKind>,
+    pub label: Option<String>,
+    pub source_port: Option<String>,
+    pub target_port: Option<String>,
+    #[schema(value_type = Option<Object>)]
+    pub style: Option<serde_json::Value>,
 }
 
 /// Remove a connection between two nodes
@@ -168,12 +318,71 @@ pub struct DisconnectNodesRequest {
     pub to_client_id: String,
 }
 
+/// Copy a selection of nodes together with the connections among them
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateNodesRequest {
+    pub client_ids: Vec<String>,
+    /// Horizontal offset for the copies; defaults to 20 within the same
+    /// project and 0 when copying to another project
+    pub dx: Option<f64>,
+    /// Vertical offset, with the same default as `dx`
+    pub dy: Option<f64>,
+    /// Project to paste into; defaults to the source project
+    pub target_project_id: Option<Uuid>,
+}
+
+/// Result of duplicating a selection
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateNodesResponse {
+    /// Project the copies were created in
+    pub project_id: Uuid,
+    /// Original client_id to the client_id of its copy
+    pub id_map: HashMap<String, String>,
+    /// The copies, in the order they were requested
+    pub nodes: Vec<CanvasNodeResponse>,
+}
+
+/// A connection in a saved canvas: either a plain `[from, to]` pair, which
+/// keeps any metadata the connection already has, or a full object
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum CanvasConnectionInput {
+    Pair([String; 2]),
+    Detailed(ConnectNodesRequest),
+}
+
+impl CanvasConnectionInput {
+    pub fn endpoints(&self) -> (&str, &str) {
+        match self {
+            CanvasConnectionInput::Pair([from, to]) => (from, to),
+            CanvasConnectionInput::Detailed(c) => (&c.from_client_id, &c.to_client_id),
+        }
+    }
+
+    pub fn into_request(self) -> ConnectNodesRequest {
+        match self {
+            CanvasConnectionInput::Pair([from_client_id, to_client_id]) => ConnectNodesRequest {
+                from_client_id,
+                to_client_id,
+                kind: None,
+                label: None,
+                source_port: None,
+                target_port: None,
+                style: None,
+            },
+            CanvasConnectionInput::Detailed(c) => c,
+        }
+    }
+}
+
 /// Bulk replace entire canvas state atomically
 #[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BulkCanvasSave {
     pub nodes: Vec<CreateNodeRequest>,
-    pub connections: Vec<[String; 2]>,
+    pub connections: Vec<CanvasConnectionInput>,
     pub zoom: Option<f64>,
     pub pan_x: Option<f64>,
     pub pan_y: Option<f64>,
@@ -183,8 +392,15 @@ pub struct BulkCanvasSave {
 #[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CanvasState {
-    pub nodes: Vec<CanvasNodeResponse>,
+    /// Nodes, trimmed to the requested fieldset
+    #[schema(value_type = Vec<CanvasNodeResponse>)]
+    pub nodes: Vec<serde_json::Value>,
+    /// [from, to] pairs, kept for clients that predate connection metadata
     pub connections: Vec<[String; 2]>,
+    /// The same connections with their kind, label, ports and style
+    pub connection_details: Vec<Connection>,
+    /// Groups, outermost first
+    pub groups: Vec<CanvasGroupResponse>,
     pub zoom: f64,
     pub pan_x: f64,
     pub pan_y: f64,
@@ -205,6 +421,8 @@ pub struct CanvasNodeResponse {
     pub width: f64,
     pub height: f64,
     pub status: NodeStatus,
+    /// When the node last changed status
+    pub status_changed_at: DateTime<Utc>,
     pub content: Option<String>,
     pub file_name: Option<String>,
     pub generated_code: Option<String>,
@@ -216,8 +434,139 @@ pub struct CanvasNodeResponse {
     pub language: Option<String>,
     pub ai_model: Option<String>,
     pub element_links: Vec<ElementLink>,
+    /// Secret values are masked
     pub env_vars: HashMap<String, String>,
+    /// Env vars whose values are encrypted; read them through the reveal endpoint
+    pub secret_env_vars: Vec<String>,
+    /// Payment `secretKey` and `webhookSecret` are masked
+    #[schema(value_type = Option<NodePayload>)]
+    pub payload: Option<serde_json::Value>,
+    /// Group (frame) the node sits in; managed through the groups endpoints
+    pub group_id: Option<Uuid>,
+    /// Uploaded assets the node uses
+    pub asset_ids: Vec<Uuid>,
     pub connected_to: Vec<String>,
+    /// Changes whenever `content` or `generatedCode` changes
+    pub content_hash: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// A node sitting in the project trash
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedNode {
+    pub client_id: String,
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    pub title: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// One entry of a node's status history
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatusChange {
+    /// Absent for the status the node was created with
+    pub from_status: Option<NodeStatus>,
+    pub to_status: NodeStatus,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// How much to delete along with a node
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DeleteNodeQuery {
+    /// Also move every node generated from it (following `parentId`) to the trash
+    pub with_descendants: Option<bool>,
+}
+
+/// Field a node list can be sorted by
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeSort {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Title,
+}
+
+impl NodeSort {
+    pub fn column(self) -> SortColumn {
+        match self {
+            NodeSort::CreatedAt => SortColumn { expr: "created_at", is_timestamp: true },
+            NodeSort::UpdatedAt => SortColumn { expr: "updated_at", is_timestamp: true },
+            NodeSort::Title => SortColumn { expr: "title", is_timestamp: false },
+        }
+    }
+}
+
+/// Filters, sorting and pagination for listing nodes
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListNodesQuery {
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    pub node_type: Option<NodeType>,
+    pub status: Option<NodeStatus>,
+    pub tag: Option<String>,
+    pub platform: Option<NodePlatform>,
+    pub picked: Option<bool>,
+    /// Only children generated from this node
+    pub parent_id: Option<String>,
+    /// Only nodes intersecting the rectangle `x1,y1,x2,y2`
+    pub bbox: Option<String>,
+    /// Comma-separated response fields to include, e.g. `title,x,y,contentHash`
+    pub fields: Option<String>,
+    /// Omit `content` and `generatedCode`; fetch them later via node-content
+    pub summary: Option<bool>,
+    /// Sort field (default created_at)
+    pub sort: Option<NodeSort>,
+    /// Sort direction (default asc)
+    pub order: Option<SortOrder>,
+    /// Cursor from the previous page's X-Next-Cursor header
+    pub cursor: Option<String>,
+    /// Page size (default 50, max 500). Without `limit` or `cursor` every match is returned
+    pub limit: Option<i64>,
+}
+
+/// Fieldset selection for endpoints returning whole canvases
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct NodeFieldsQuery {
+    /// Only nodes intersecting the rectangle `x1,y1,x2,y2`, and connections
+    /// with both endpoints among them
+    pub bbox: Option<String>,
+    /// Comma-separated node fields to include, e.g. `title,x,y,contentHash`
+    pub fields: Option<String>,
+    /// Omit `content` and `generatedCode`; fetch them later via node-content
+    pub summary: Option<bool>,
+}
+
+/// Fetch heavy node bodies on demand
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeContentRequest {
+    pub client_ids: Vec<String>,
+    /// contentHash values the client already holds, keyed by client_id;
+    /// matching nodes are returned without bodies
+    pub known_hashes: Option<HashMap<String, String>>,
+}
+
+/// Heavy bodies of a single node
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeContentResponse {
+    pub client_id: String,
+    /// Payment code has its secrets masked before it is sent
+    #[serde(skip)]
+    pub node_type: NodeType,
+    pub content_hash: String,
+    /// True when the client's known hash is current and bodies were skipped
+    pub unchanged: bool,
+    pub content: Option<String>,
+    pub generated_code: Option<String>,
+}
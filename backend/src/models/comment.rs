@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, FromRow)]
+pub struct CommentThread {
+    pub id: Uuid,
+    pub client_id: String,
+    pub selector: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+pub struct Comment {
+    pub id: Uuid,
+    pub thread_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub body: String,
+    pub edited_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A project member mentioned in a comment
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MentionedUser {
+    #[serde(skip)]
+    pub comment_id: Uuid,
+    pub user_id: Uuid,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentResponse {
+    pub id: Uuid,
+    /// Absent once the author's account is deleted
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    pub body: String,
+    pub mentions: Vec<MentionedUser>,
+    /// Set when the body was changed after posting
+    pub edited_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A discussion anchored to a node, or to an element inside a design node
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentThreadResponse {
+    pub id: Uuid,
+    /// client_id of the node the thread is attached to
+    pub client_id: String,
+    /// CSS selector of the element the thread is attached to
+    pub selector: Option<String>,
+    pub created_by: Option<Uuid>,
+    pub resolved: bool,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolved_by: Option<Uuid>,
+    /// Oldest first
+    pub comments: Vec<CommentResponse>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Filters for listing a project's threads
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListThreadsQuery {
+    /// Only threads on this node
+    pub client_id: Option<String>,
+    /// Only resolved (`true`) or open (`false`) threads
+    pub resolved: Option<bool>,
+}
+
+/// Start a thread with its first comment
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateThreadRequest {
+    pub client_id: String,
+    /// CSS selector of an element inside a design node
+    pub selector: Option<String>,
+    /// Comment text; `@name` or `@email` mentions a project member
+    pub body: String,
+}
+
+/// Reply to a thread, or edit a comment
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentBodyRequest {
+    pub body: String,
+}
+
+/// Resolve or reopen a thread
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateThreadRequest {
+    pub resolved: bool,
+}
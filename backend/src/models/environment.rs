@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::node_payload::DeployEnvironment;
+
+/// Environment to resolve a node's variables for
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct EffectiveEnvQuery {
+    pub environment: DeployEnvironment,
+}
+
+/// The variables a node sees in one environment, and where each came from
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveEnvironment {
+    pub client_id: String,
+    pub environment: DeployEnvironment,
+    /// Bound env nodes that apply, strongest first
+    pub sources: Vec<EnvSource>,
+    /// Sorted by name; secret values are masked
+    pub variables: Vec<EffectiveVariable>,
+    pub conflicts: Vec<EnvConflict>,
+    pub missing: Vec<MissingVariable>,
+    pub broken_bases: Vec<BrokenEnvBase>,
+}
+
+/// A bound env node and the bases it inherits from
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvSource {
+    pub client_id: String,
+    pub title: String,
+    /// Absent when the node applies to every environment
+    pub environment: Option<DeployEnvironment>,
+    /// client_ids of its bases, nearest first
+    pub inherits: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveVariable {
+    pub name: String,
+    pub value: String,
+    pub secret: bool,
+    /// Env node the value comes from
+    pub source: String,
+    /// Env nodes whose value for the same name was overridden
+    pub overrides: Vec<String>,
+}
+
+/// Bound env nodes of equal precedence that disagree on a value
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvConflict {
+    pub name: String,
+    /// Env nodes that define the name, the one whose value was used first
+    pub sources: Vec<String>,
+}
+
+/// A required variable with no value
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingVariable {
+    pub name: String,
+    /// Env nodes that declare it required
+    pub required_by: Vec<String>,
+}
+
+/// An env node whose base cannot be followed
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenEnvBase {
+    pub client_id: String,
+    pub base: String,
+    pub reason: BrokenBaseReason,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenBaseReason {
+    /// No live node has that client_id
+    Missing,
+    /// The base is not an env node
+    NotEnv,
+    /// Following bases leads back to this node
+    Cycle,
+}
+
+/// A `.env` file to load into an env node
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DotenvImportRequest {
+    /// Text of the file
+    pub content: String,
+    /// Drop variables the file does not mention (default: merge into them)
+    pub replace: Option<bool>,
+    /// Imported names to store encrypted; variables that are already secret stay secret
+    pub secret_env_vars: Option<Vec<String>>,
+}
+
+/// Form of a `.env` export
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DotenvExportQuery {
+    /// Emit a `.env.example` with every value blanked
+    pub example: Option<bool>,
+    /// Include decrypted secret values; each one is recorded as a reveal.
+    /// Otherwise secret values are left empty.
+    pub include_secrets: Option<bool>,
+}
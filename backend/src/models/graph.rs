@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Options for whole-canvas analysis
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct GraphAnalysisQuery {
+    /// Comma-separated client_ids to measure reachability from; defaults to
+    /// every node without incoming connections
+    pub roots: Option<String>,
+    /// Comma-separated connection kinds to follow (default `data_flow,depends_on`;
+    /// navigation and env bindings are not build order and routinely loop)
+    pub kinds: Option<String>,
+}
+
+/// Structural report over a project's connections
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphAnalysis {
+    pub node_count: usize,
+    pub connection_count: usize,
+    /// True when the canvas has no cycles
+    pub is_dag: bool,
+    /// Groups of nodes that form a cycle (strongly connected components,
+    /// including nodes connected to themselves)
+    pub cycles: Vec<Vec<String>>,
+    /// Node order in which every connection points forward; absent when
+    /// the canvas has a cycle
+    pub topological_order: Option<Vec<String>>,
+    /// Nodes with no connections at all
+    pub orphans: Vec<String>,
+    /// Nodes not reachable from the roots
+    pub unreachable: Vec<String>,
+}
+
+/// Limit for ancestor and descendant walks
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RelatedNodesQuery {
+    /// Maximum number of hops to follow (default unlimited)
+    pub max_depth: Option<usize>,
+    /// Comma-separated connection kinds to follow (default `data_flow,depends_on`;
+    /// navigation and env bindings are not build order and routinely loop)
+    pub kinds: Option<String>,
+}
+
+/// A node reached from another and how many hops away it is
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedNode {
+    pub client_id: String,
+    pub depth: usize,
+}
+
+/// Endpoints of a shortest-path query
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ShortestPathQuery {
+    pub from: String,
+    pub to: String,
+    /// Follow connections in both directions
+    pub undirected: Option<bool>,
+    /// Comma-separated connection kinds to follow (default `data_flow,depends_on`;
+    /// navigation and env bindings are not build order and routinely loop)
+    pub kinds: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortestPath {
+    /// client_ids from `from` to `to`, inclusive
+    pub path: Vec<String>,
+    pub hops: usize,
+}
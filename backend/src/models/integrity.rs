@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::element_links::SelectorProblem;
+
+/// Options for an integrity repair run
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RepairQuery {
+    /// Report problems without fixing them
+    pub dry_run: Option<bool>,
+}
+
+/// An element link whose target node does not exist
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DanglingElementLink {
+    /// client_id of the node holding the link
+    pub client_id: String,
+    pub selector: String,
+    pub target_node_id: String,
+}
+
+/// Orphans found (and, unless dry-run, removed) in a project
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub dry_run: bool,
+    /// Connections with a missing endpoint, as [from, to] pairs
+    pub orphan_connections: Vec<[String; 2]>,
+    pub dangling_element_links: Vec<DanglingElementLink>,
+}
+
+/// Why an element link no longer works
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BrokenLinkReason {
+    /// The node holding the link has no HTML
+    NoContent,
+    /// The selector is not valid CSS
+    InvalidSelector,
+    /// The selector matches nothing in the node's HTML
+    NoMatch,
+    /// The target node does not exist
+    MissingTarget,
+    /// The target node is in the trash
+    TargetInTrash,
+}
+
+impl From<SelectorProblem> for BrokenLinkReason {
+    fn from(problem: SelectorProblem) -> Self {
+        match problem {
+            SelectorProblem::NoContent => Self::NoContent,
+            SelectorProblem::InvalidSelector => Self::InvalidSelector,
+            SelectorProblem::NoMatch => Self::NoMatch,
+        }
+    }
+}
+
+/// An element link that fails at least one check
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenElementLink {
+    /// client_id of the node holding the link
+    pub client_id: String,
+    pub selector: String,
+    pub label: String,
+    pub target_node_id: String,
+    pub reasons: Vec<BrokenLinkReason>,
+}
+
+/// Element links of a project checked against their designs and targets
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLinksReport {
+    /// Number of links checked across all live nodes
+    pub checked: usize,
+    pub broken: Vec<BrokenElementLink>,
+}
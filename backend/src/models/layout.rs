@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Layout strategy
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutAlgorithm {
+    /// Layered DAG built from connections, sources at the top
+    Layered,
+    /// Tree following each node's parentId
+    Tree,
+    /// Square-ish grid in creation order
+    Grid,
+}
+
+/// Compute (and optionally apply) positions for a project's nodes
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutRequest {
+    pub algorithm: LayoutAlgorithm,
+    /// Nodes to lay out; all live nodes when omitted. The selection keeps its
+    /// current top-left corner unless the result would overlap unselected
+    /// nodes, in which case it moves right or down into free space.
+    pub client_ids: Option<Vec<String>>,
+    /// Write the positions in one transaction instead of only proposing them
+    pub apply: Option<bool>,
+    /// Horizontal gap between nodes (default 40)
+    pub node_spacing: Option<f64>,
+    /// Vertical gap between layers and rows (default 80)
+    pub layer_spacing: Option<f64>,
+}
+
+/// A computed node position
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodePosition {
+    pub client_id: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutResponse {
+    /// Whether the positions were written to the nodes
+    pub applied: bool,
+    pub positions: Vec<NodePosition>,
+}
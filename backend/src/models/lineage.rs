@@ -0,0 +1,47 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::models::canvas_node::{NodeStatus, NodeType};
+
+/// A node in a `parentId` tree with the nodes generated from it
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LineageNode {
+    pub client_id: String,
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    pub title: String,
+    pub status: NodeStatus,
+    /// Generations away from the node the query started at
+    pub depth: i32,
+    pub children: Vec<LineageNode>,
+}
+
+/// A live node reached by a lineage walk
+#[derive(Debug, FromRow)]
+pub struct LineageRow {
+    pub client_id: String,
+    pub parent_id: Option<String>,
+    pub depth: i32,
+    pub node_type: NodeType,
+    pub title: String,
+    pub status: NodeStatus,
+}
+
+/// Limit for descendant walks
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct LineageQuery {
+    /// Maximum number of generations to follow (default unlimited)
+    pub max_depth: Option<i32>,
+}
+
+/// Move a node under another parent
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SetParentRequest {
+    /// client_id of the new parent; null makes the node a root
+    pub parent_id: Option<String>,
+}
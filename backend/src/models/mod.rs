@@ -1,4 +1,17 @@
+pub mod asset;
+pub mod canvas_group;
 pub mod canvas_node;
+pub mod comment;
+pub mod environment;
+pub mod graph;
+pub mod integrity;
+pub mod layout;
+pub mod lineage;
+pub mod node_lock;
+pub mod node_payload;
+pub mod node_revision;
 pub mod project;
+pub mod search;
+pub mod secret;
 pub mod ui_variation;
 pub mod user;
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// An active edit lock on a node
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeLock {
+    pub client_id: String,
+    pub holder_id: Uuid,
+    pub holder_name: String,
+    pub acquired_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Identifies the holding session; only ever sent to that session
+    #[serde(skip)]
+    pub token: Uuid,
+}
+
+/// A lock as returned to the session holding it
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldLock {
+    #[serde(flatten)]
+    pub lock: NodeLock,
+    /// Send as `X-Lock-Token` when renewing, releasing, taking further locks
+    /// and writing to locked nodes
+    pub lock_token: Uuid,
+}
+
+/// Acquire or renew a lock
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LockRequest {
+    /// Lock lifetime from now; defaults to the server's configured TTL
+    pub ttl_seconds: Option<i64>,
+}
+
+/// Options for releasing a lock
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ReleaseLockQuery {
+    /// Break a lock held by someone else (project owners only)
+    pub force: Option<bool>,
+}
@@ -0,0 +1,510 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::models::canvas_node::NodeType;
+
+/// Structured data edited by the API, CLI, database, payment and env editors.
+/// Which variant applies is decided by the node's type, not by a tag.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum NodePayload {
+    /// `api` nodes: the endpoint list
+    Api(Vec<ApiEndpoint>),
+    /// `cli` nodes: the step flow
+    Cli(Vec<CliStep>),
+    /// `database` nodes: tables and relations
+    Database(DbSchema),
+    /// `payment` nodes: provider settings and plans
+    Payment(PaymentConfig),
+    /// `env` nodes: which environment the variables are for and what they inherit
+    Env(EnvConfig),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiAuth {
+    None,
+    Bearer,
+    ApiKey,
+    Oauth2,
+}
+
+/// A header, query or path parameter of an endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiParam {
+    pub id: String,
+    pub key: String,
+    #[serde(rename = "type")]
+    pub param_type: String,
+    pub required: bool,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiEndpoint {
+    pub id: String,
+    pub method: HttpMethod,
+    /// Route path, starting with `/`
+    pub path: String,
+    pub summary: String,
+    pub tag: String,
+    pub headers: Vec<ApiParam>,
+    pub query_params: Vec<ApiParam>,
+    pub path_params: Vec<ApiParam>,
+    /// Example or JSON schema of the request body
+    pub request_body: String,
+    /// Example or JSON schema of the response body
+    pub response_body: String,
+    pub status_code: u16,
+    pub auth: ApiAuth,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum CliStepKind {
+    Input,
+    Output,
+    If,
+    Else,
+    Loop,
+    Variable,
+    Function,
+    Command,
+    Flag,
+    Arg,
+    FileRead,
+    FileWrite,
+    EnvVar,
+    Exit,
+    Error,
+    Spinner,
+    PromptSelect,
+    PromptConfirm,
+    TableOutput,
+    Progress,
+    Pipe,
+    TryCatch,
+    Delay,
+    HttpRequest,
+    DbQuery,
+    Comment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CliStep {
+    pub id: String,
+    pub kind: CliStepKind,
+    pub label: String,
+    /// Kind-specific settings, e.g. `command`, `message`, `default`
+    #[serde(default)]
+    pub config: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DbEngine {
+    #[default]
+    Sql,
+    Nosql,
+    Vector,
+    Graph,
+    Timeseries,
+    Keyvalue,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum DbRelationType {
+    OneToOne,
+    OneToMany,
+    ManyToMany,
+    Directed,
+    Bidirectional,
+    Weighted,
+}
+
+/// Points a column at a column of another table
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbColumnRef {
+    pub table_id: String,
+    pub column_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbColumn {
+    pub id: String,
+    pub name: String,
+    /// Engine-specific column type, e.g. `uuid`, `objectId`, `embedding`
+    #[serde(rename = "type")]
+    pub column_type: String,
+    pub is_primary: bool,
+    pub is_nullable: bool,
+    pub is_unique: bool,
+    #[serde(default)]
+    pub default_value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reference: Option<DbColumnRef>,
+    /// Vector dimension for vector engines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimension: Option<u32>,
+    /// Relationship label for graph engines
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rel_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbTable {
+    pub id: String,
+    pub name: String,
+    pub columns: Vec<DbColumn>,
+    /// Position inside the schema editor
+    pub x: f64,
+    pub y: f64,
+    pub color: String,
+    /// Engine-specific entity label, e.g. collection or index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbRelation {
+    pub id: String,
+    pub from_table_id: String,
+    pub from_column_id: String,
+    pub to_table_id: String,
+    pub to_column_id: String,
+    #[serde(rename = "type")]
+    pub relation_type: DbRelationType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DbSchema {
+    pub tables: Vec<DbTable>,
+    #[serde(default)]
+    pub relations: Vec<DbRelation>,
+    #[serde(default)]
+    pub engine: DbEngine,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub enum PaymentProvider {
+    Stripe,
+    PayPal,
+    Paddle,
+    LemonSqueezy,
+    Adyen,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub enum PaymentEnvironment {
+    Test,
+    Live,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum PaymentSystem {
+    Subscription,
+    #[serde(rename = "One-time")]
+    OneTime,
+    Lifetime,
+    Free,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub enum BillingInterval {
+    Monthly,
+    Yearly,
+    Quarterly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentPlan {
+    pub id: String,
+    pub name: String,
+    pub price: f64,
+    /// ISO 4217 code, e.g. `USD`
+    pub currency: String,
+    pub system_type: PaymentSystem,
+    /// Required for subscriptions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<BillingInterval>,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub is_popular: bool,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentConfig {
+    pub provider: PaymentProvider,
+    pub environment: PaymentEnvironment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+    pub plans: Vec<PaymentPlan>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DeployEnvironment {
+    Development,
+    Staging,
+    Production,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvConfig {
+    /// Environment the values are for; absent applies to every environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<DeployEnvironment>,
+    /// client_id of the env node whose variables this one inherits and overrides
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Variables that must have a non-empty value once resolved
+    #[serde(default)]
+    pub required: Vec<String>,
+}
+
+impl NodePayload {
+    /// Parse and check a raw payload against the schema of `node_type`.
+    /// Errors name the offending field, e.g. `payload[2].method: unknown variant`.
+    pub fn parse(node_type: &NodeType, raw: serde_json::Value) -> Result<Self> {
+        let payload = match node_type {
+            NodeType::Api => NodePayload::Api(deserialize(raw)?),
+            NodeType::Cli => NodePayload::Cli(deserialize(raw)?),
+            NodeType::Database => NodePayload::Database(deserialize(raw)?),
+            NodeType::Payment => NodePayload::Payment(deserialize(raw)?),
+            NodeType::Env => NodePayload::Env(deserialize(raw)?),
+            other => {
+                let name = serde_json::to_value(other).unwrap_or_default();
+                return Err(AppError::Validation(format!(
+                    "payload: {} nodes do not take a structured payload",
+                    name.as_str().unwrap_or_default()
+                )));
+            }
+        };
+
+        let mut errors = Vec::new();
+        match &payload {
+            NodePayload::Api(endpoints) => check_api(endpoints, &mut errors),
+            NodePayload::Cli(steps) => check_cli(steps, &mut errors),
+            NodePayload::Database(schema) => check_database(schema, &mut errors),
+            NodePayload::Payment(config) => check_payment(config, &mut errors),
+            NodePayload::Env(config) => check_env(config, &mut errors),
+        }
+        if !errors.is_empty() {
+            return Err(AppError::Validation(errors.join("; ")));
+        }
+        Ok(payload)
+    }
+
+    /// Whether the node type's editor mirrors its payload as JSON into
+    /// `generated_code`, so one can be derived from the other
+    pub fn mirrored_in_code(node_type: &NodeType) -> bool {
+        matches!(
+            node_type,
+            NodeType::Api | NodeType::Cli | NodeType::Database | NodeType::Payment
+        )
+    }
+
+    /// Derive a payload from the JSON in a node's generated code, checked like
+    /// a request payload. No code means no payload.
+    pub fn from_code(
+        node_type: &NodeType,
+        code: Option<&str>,
+    ) -> Result<Option<serde_json::Value>> {
+        let Some(code) = code else {
+            return Ok(None);
+        };
+        let raw: serde_json::Value = serde_json::from_str(code)
+            .map_err(|e| AppError::Validation(format!("generatedCode: not valid JSON: {e}")))?;
+        Self::validate(node_type, Some(&raw))
+    }
+
+    /// Validate an optional request payload and normalise it for storage
+    pub fn validate(
+        node_type: &NodeType,
+        raw: Option<&serde_json::Value>,
+    ) -> Result<Option<serde_json::Value>> {
+        match raw {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(raw) => {
+                let payload = Self::parse(node_type, raw.clone())?;
+                Ok(Some(serde_json::to_value(payload).unwrap_or_default()))
+            }
+        }
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(raw: serde_json::Value) -> Result<T> {
+    serde_path_to_error::deserialize(raw).map_err(|e| {
+        let path = e.path().to_string();
+        let field = match path.as_str() {
+            "." => "payload".to_string(),
+            p if p.starts_with('[') => format!("payload{p}"),
+            p => format!("payload.{p}"),
+        };
+        AppError::Validation(format!("{field}: {}", e.into_inner()))
+    })
+}
+
+/// Record an error for every id seen more than once
+fn check_unique_ids<'a>(
+    prefix: &str,
+    ids: impl Iterator<Item = &'a str>,
+    errors: &mut Vec<String>,
+) {
+    let mut seen = HashSet::new();
+    for (i, id) in ids.enumerate() {
+        if id.is_empty() {
+            errors.push(format!("{prefix}[{i}].id: must not be empty"));
+        } else if !seen.insert(id) {
+            errors.push(format!("{prefix}[{i}].id: duplicate id '{id}'"));
+        }
+    }
+}
+
+fn check_api(endpoints: &[ApiEndpoint], errors: &mut Vec<String>) {
+    check_unique_ids("payload", endpoints.iter().map(|e| e.id.as_str()), errors);
+
+    let mut routes = HashSet::new();
+    for (i, ep) in endpoints.iter().enumerate() {
+        if !ep.path.starts_with('/') {
+            errors.push(format!("payload[{i}].path: must start with '/'"));
+        } else if !routes.insert((ep.method, ep.path.as_str())) {
+            errors.push(format!(
+                "payload[{i}].path: duplicate route {:?} {}",
+                ep.method, ep.path
+            ));
+        }
+        if !(100..=599).contains(&ep.status_code) {
+            errors.push(format!("payload[{i}].statusCode: must be between 100 and 599"));
+        }
+    }
+}
+
+fn check_cli(steps: &[CliStep], errors: &mut Vec<String>) {
+    check_unique_ids("payload", steps.iter().map(|s| s.id.as_str()), errors);
+}
+
+fn check_database(schema: &DbSchema, errors: &mut Vec<String>) {
+    check_unique_ids("payload.tables", schema.tables.iter().map(|t| t.id.as_str()), errors);
+    check_unique_ids(
+        "payload.relations",
+        schema.relations.iter().map(|r| r.id.as_str()),
+        errors,
+    );
+
+    let columns: HashMap<&str, HashSet<&str>> = schema
+        .tables
+        .iter()
+        .map(|t| (t.id.as_str(), t.columns.iter().map(|c| c.id.as_str()).collect()))
+        .collect();
+    let resolves = |table: &str, column: &str| {
+        columns.get(table).is_some_and(|cols| cols.contains(column))
+    };
+
+    for (t, table) in schema.tables.iter().enumerate() {
+        if table.name.trim().is_empty() {
+            errors.push(format!("payload.tables[{t}].name: must not be empty"));
+        }
+        let prefix = format!("payload.tables[{t}].columns");
+        check_unique_ids(&prefix, table.columns.iter().map(|c| c.id.as_str()), errors);
+
+        for (c, column) in table.columns.iter().enumerate() {
+            if column.name.trim().is_empty() {
+                errors.push(format!("{prefix}[{c}].name: must not be empty"));
+            }
+            if column.dimension == Some(0) {
+                errors.push(format!("{prefix}[{c}].dimension: must be positive"));
+            }
+            if let Some(r) = &column.reference {
+                if !resolves(&r.table_id, &r.column_id) {
+                    errors.push(format!(
+                        "{prefix}[{c}].reference: no column '{}' in table '{}'",
+                        r.column_id, r.table_id
+                    ));
+                }
+            }
+        }
+    }
+
+    for (i, rel) in schema.relations.iter().enumerate() {
+        if !resolves(&rel.from_table_id, &rel.from_column_id) {
+            errors.push(format!(
+                "payload.relations[{i}].fromColumnId: no column '{}' in table '{}'",
+                rel.from_column_id, rel.from_table_id
+            ));
+        }
+        if !resolves(&rel.to_table_id, &rel.to_column_id) {
+            errors.push(format!(
+                "payload.relations[{i}].toColumnId: no column '{}' in table '{}'",
+                rel.to_column_id, rel.to_table_id
+            ));
+        }
+    }
+}
+
+fn check_payment(config: &PaymentConfig, errors: &mut Vec<String>) {
+    check_unique_ids("payload.plans", config.plans.iter().map(|p| p.id.as_str()), errors);
+
+    for (i, plan) in config.plans.iter().enumerate() {
+        let prefix = format!("payload.plans[{i}]");
+        if !plan.price.is_finite() || plan.price < 0.0 {
+            errors.push(format!("{prefix}.price: must be a non-negative number"));
+        }
+        if plan.system_type == PaymentSystem::Free && plan.price > 0.0 {
+            errors.push(format!("{prefix}.price: free plans must cost 0"));
+        }
+        if plan.currency.len() != 3 || !plan.currency.bytes().all(|b| b.is_ascii_uppercase()) {
+            errors.push(format!("{prefix}.currency: must be a three-letter ISO code"));
+        }
+        if plan.system_type == PaymentSystem::Subscription && plan.interval.is_none() {
+            errors.push(format!("{prefix}.interval: required for subscriptions"));
+        }
+    }
+}
+
+fn check_env(config: &EnvConfig, errors: &mut Vec<String>) {
+    if config.base.as_deref().is_some_and(|b| b.trim().is_empty()) {
+        errors.push("payload.base: must not be empty".to_string());
+    }
+    let mut seen = HashSet::new();
+    for (i, name) in config.required.iter().enumerate() {
+        if name.trim().is_empty() {
+            errors.push(format!("payload.required[{i}]: must not be empty"));
+        } else if !seen.insert(name.as_str()) {
+            errors.push(format!("payload.required[{i}]: duplicate variable '{name}'"));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// The node fields that are tracked in revision history
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct RevisionFields {
+    pub title: String,
+    pub description: String,
+    pub content: Option<String>,
+    pub generated_code: Option<String>,
+}
+
+impl RevisionFields {
+    /// Field names paired with their text, NULL bodies as empty
+    pub fn named(&self) -> [(&'static str, &str); 4] {
+        [
+            ("title", &self.title),
+            ("description", &self.description),
+            ("content", self.content.as_deref().unwrap_or_default()),
+            (
+                "generatedCode",
+                self.generated_code.as_deref().unwrap_or_default(),
+            ),
+        ]
+    }
+}
+
+/// One entry in a node's history, without the field bodies
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeRevisionSummary {
+    pub revision: i32,
+    /// Absent for the baseline captured before the first tracked edit
+    pub author_id: Option<Uuid>,
+    pub author_name: Option<String>,
+    /// Tracked fields that differ from the previous revision
+    pub changed_fields: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Paging for a node's history, newest first
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListRevisionsQuery {
+    /// Only revisions older than this revision number
+    pub before: Option<i32>,
+    /// Page size (default 50, max 500)
+    pub limit: Option<i64>,
+}
+
+/// Revisions to compare
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct RevisionDiffQuery {
+    pub from: i32,
+    /// Defaults to the latest revision
+    pub to: Option<i32>,
+}
+
+/// Unified diff of one field
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub diff: String,
+}
+
+/// Differences between two revisions of a node; unchanged fields are omitted
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionDiff {
+    pub from: i32,
+    pub to: i32,
+    pub fields: Vec<FieldDiff>,
+}
@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::pagination::{SortColumn, SortOrder};
+
 /// A canvas project owned by a user
 #[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -22,6 +24,8 @@ pub struct Project {
     pub ai_model: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set when the project has been moved to the trash
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 /// Create a new project
@@ -44,3 +48,42 @@ pub struct UpdateProjectRequest {
     pub pan_y: Option<f64>,
     pub ai_model: Option<String>,
 }
+
+/// Field a project list can be sorted by
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectSort {
+    #[default]
+    UpdatedAt,
+    CreatedAt,
+    Name,
+}
+
+impl ProjectSort {
+    pub fn column(self) -> SortColumn {
+        match self {
+            ProjectSort::UpdatedAt => SortColumn { expr: "updated_at", is_timestamp: true },
+            ProjectSort::CreatedAt => SortColumn { expr: "created_at", is_timestamp: true },
+            ProjectSort::Name => SortColumn { expr: "name", is_timestamp: false },
+        }
+    }
+}
+
+/// Filters, sorting and pagination for listing projects
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListProjectsQuery {
+    /// Only projects whose name starts with this prefix (case-insensitive)
+    pub name_prefix: Option<String>,
+    /// Only projects updated at or after this instant
+    pub updated_since: Option<DateTime<Utc>>,
+    /// Sort field (default updated_at)
+    pub sort: Option<ProjectSort>,
+    /// Sort direction (default desc)
+    pub order: Option<SortOrder>,
+    /// Cursor from the previous page's X-Next-Cursor header
+    pub cursor: Option<String>,
+    /// Page size (default 50, max 500). Without `limit` or `cursor` every match is returned
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::canvas_node::{NodePlatform, NodeType};
+
+/// Query parameters for full-text search
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Search text; supports quoted phrases, `or` and `-exclusions`
+    pub q: String,
+    /// Restrict results to a single project
+    pub project_id: Option<Uuid>,
+    /// Only return nodes of this type
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    pub node_type: Option<NodeType>,
+    /// Only return nodes targeting this platform
+    pub platform: Option<NodePlatform>,
+    /// Maximum number of results (default 20, max 100)
+    pub limit: Option<i64>,
+}
+
+/// A single ranked search hit
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    /// "project" or "node"
+    pub kind: String,
+    pub project_id: Uuid,
+    /// client_id of the matched node; absent for project hits
+    pub client_id: Option<String>,
+    #[serde(rename = "type")]
+    pub node_type: Option<NodeType>,
+    pub platform: Option<NodePlatform>,
+    pub title: String,
+    /// Matching excerpt as escaped HTML: tags in the body are dropped, its text
+    /// is escaped, and only the `<mark>` tags around hits are markup
+    pub snippet: String,
+    pub rank: f32,
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// Plaintext of one node secret
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RevealedSecret {
+    /// `env.<NAME>`, `payment.secretKey` or `payment.webhookSecret`
+    pub name: String,
+    pub value: String,
+}
+
+/// One audited reveal of a node secret
+#[derive(Debug, Serialize, FromRow, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretReveal {
+    pub client_id: String,
+    pub secret_name: String,
+    /// Absent once the user has been deleted
+    pub user_id: Option<Uuid>,
+    pub user_name: Option<String>,
+    pub revealed_at: DateTime<Utc>,
+}
+
+/// Filter for the reveal audit log, newest first
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct SecretRevealsQuery {
+    /// Only reveals of this node's secrets
+    pub client_id: Option<String>,
+    /// Page size (default 50, max 500)
+    pub limit: Option<i64>,
+}
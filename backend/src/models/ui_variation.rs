@@ -1,9 +1,13 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::postgres::{PgHasArrayType, PgTypeInfo};
 use sqlx::FromRow;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use crate::models::canvas_node::CanvasNodeResponse;
+use crate::pagination::{SortColumn, SortOrder};
+
 /// Category of UI variation for assembly purposes
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "variation_category", rename_all = "snake_case")]
@@ -18,6 +22,28 @@ pub enum VariationCategory {
     Mobile,
 }
 
+impl PgHasArrayType for VariationCategory {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_variation_category")
+    }
+}
+
+impl VariationCategory {
+    /// Assembly role of a node showing this variation; mobile is a platform,
+    /// not a section of a page
+    pub fn page_role(&self) -> Option<&'static str> {
+        match self {
+            Self::Header => Some("header"),
+            Self::Hero => Some("hero"),
+            Self::Features => Some("features"),
+            Self::Pricing => Some("pricing"),
+            Self::Footer => Some("footer"),
+            Self::Dashboard => Some("dashboard"),
+            Self::Mobile => None,
+        }
+    }
+}
+
 /// A generated UI variation for a source node
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -30,7 +56,21 @@ pub struct UiVariation {
     pub preview_html: String,
     pub code: String,
     pub category: VariationCategory,
+    /// Hash of `preview_html` and `code`; a source node stores each body once
+    pub content_hash: String,
     pub created_at: DateTime<Utc>,
+    /// When this variation was applied to its source node; at most one
+    /// variation per source node is selected
+    pub selected_at: Option<DateTime<Utc>>,
+    pub selected_by: Option<Uuid>,
+}
+
+/// A variation that was applied and the node it now fills
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationSelection {
+    pub variation: UiVariation,
+    pub node: CanvasNodeResponse,
 }
 
 /// Save multiple UI variations for a node
@@ -51,3 +91,39 @@ pub struct VariationPayload {
     pub code: String,
     pub category: VariationCategory,
 }
+
+/// Field a variation list can be sorted by
+#[derive(Debug, Clone, Copy, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VariationSort {
+    #[default]
+    CreatedAt,
+    Label,
+}
+
+impl VariationSort {
+    pub fn column(self) -> SortColumn {
+        match self {
+            VariationSort::CreatedAt => SortColumn { expr: "v.created_at", is_timestamp: true },
+            VariationSort::Label => SortColumn { expr: "v.label", is_timestamp: false },
+        }
+    }
+}
+
+/// Filters, sorting and pagination for listing variations
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct ListVariationsQuery {
+    /// Only variations generated for this node
+    pub source_node_client_id: Option<String>,
+    pub category: Option<VariationCategory>,
+    /// Sort field (default created_at)
+    pub sort: Option<VariationSort>,
+    /// Sort direction (default desc)
+    pub order: Option<SortOrder>,
+    /// Cursor from the previous page's X-Next-Cursor header
+    pub cursor: Option<String>,
+    /// Page size (default 50, max 500). Without `limit` or `cursor` every match is returned
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,188 @@
+use axum::{
+    http::{HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+
+pub const DEFAULT_LIMIT: i64 = 50;
+pub const MAX_LIMIT: i64 = 500;
+
+static TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+static NEXT_CURSOR: HeaderName = HeaderName::from_static("x-next-cursor");
+
+/// Sort direction for list endpoints
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// A column a list endpoint can be ordered by
+#[derive(Debug, Clone, Copy)]
+pub struct SortColumn {
+    pub expr: &'static str,
+    pub is_timestamp: bool,
+}
+
+/// Opaque keyset cursor: the sort value and id of the last row of a page
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Cursor {
+    pub value: String,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    pub fn timestamp(value: DateTime<Utc>, id: Uuid) -> Self {
+        Self {
+            value: value.to_rfc3339_opts(SecondsFormat::Micros, true),
+            id,
+        }
+    }
+
+    pub fn text(value: &str, id: Uuid) -> Self {
+        Self {
+            value: value.to_string(),
+            id,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        BASE64_URL.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(raw: &str) -> Result<Self> {
+        BASE64_URL
+            .decode(raw)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| AppError::Validation("Invalid pagination cursor".into()))
+    }
+}
+
+/// Clamp a requested page size into the allowed range
+pub fn page_limit(limit: Option<i64>) -> i64 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+/// Page size for list endpoints that predate pagination: callers that send
+/// neither `limit` nor `cursor` keep getting every match in one response
+pub fn list_limit(limit: Option<i64>, cursor: Option<&Cursor>) -> Option<i64> {
+    if limit.is_none() && cursor.is_none() {
+        None
+    } else {
+        Some(page_limit(limit))
+    }
+}
+
+/// Escape LIKE wildcards so user input matches literally
+pub fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Append the keyset condition, ordering and limit to a query whose WHERE
+/// clause is already open. One extra row is fetched to detect a next page.
+/// A cursor whose value doesn't fit the sort column is a validation error.
+pub fn push_page(
+    qb: &mut QueryBuilder<'_, Postgres>,
+    column: SortColumn,
+    id_column: &str,
+    order: SortOrder,
+    cursor: Option<Cursor>,
+    limit: Option<i64>,
+) -> Result<()> {
+    let (cmp, dir) = match order {
+        SortOrder::Asc => (">", "ASC"),
+        SortOrder::Desc => ("<", "DESC"),
+    };
+
+    if let Some(cursor) = cursor {
+        qb.push(format!(" AND ({}, {id_column}) {cmp} (", column.expr));
+        if column.is_timestamp {
+            let value = DateTime::parse_from_rfc3339(&cursor.value)
+                .map_err(|_| {
+                    AppError::Validation("Pagination cursor does not match the sort field".into())
+                })?
+                .with_timezone(&Utc);
+            qb.push_bind(value);
+        } else {
+            qb.push_bind(cursor.value);
+        }
+        qb.push(", ");
+        qb.push_bind(cursor.id);
+        qb.push(")");
+    }
+
+    qb.push(format!(" ORDER BY {} {dir}, {id_column} {dir}", column.expr));
+    if let Some(limit) = limit {
+        qb.push(" LIMIT ");
+        qb.push_bind(limit + 1);
+    }
+    Ok(())
+}
+
+/// One page of a list endpoint, returned as a JSON array with the total
+/// match count and the next cursor carried in response headers
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Build a page from `limit + 1` fetched rows, or from every row when
+    /// no limit applies
+    pub fn from_rows(
+        mut rows: Vec<T>,
+        limit: Option<i64>,
+        total: i64,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let next_cursor = match limit {
+            Some(limit) if rows.len() as i64 > limit => {
+                rows.truncate(limit as usize);
+                rows.last().map(|row| cursor_of(row).encode())
+            }
+            _ => None,
+        };
+        Self {
+            items: rows,
+            total,
+            next_cursor,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Page<T> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.items).into_response();
+        let headers = response.headers_mut();
+        headers.insert(TOTAL_COUNT.clone(), HeaderValue::from(self.total));
+        if let Some(cursor) = self
+            .next_cursor
+            .and_then(|c| HeaderValue::from_str(&c).ok())
+        {
+            headers.insert(NEXT_CURSOR.clone(), cursor);
+        }
+        response
+    }
+}
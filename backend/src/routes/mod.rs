@@ -1,4 +1,5 @@
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{delete, get, patch, post, put},
     Json, Router,
 };
@@ -7,23 +8,66 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
-    handlers::{ai_proxy, auth, nodes, projects, variations},
+    handlers::{
+        ai_proxy, assets, auth, comments, environments, graph, groups, integrity, layout,
+        lineage, locks, node_status, nodes, projects, revisions, search, secrets, trash,
+        variations,
+    },
     models::{
+        asset::{Asset, AssetUpload},
+        canvas_group::{
+            CanvasGroupResponse, CreateGroupRequest, MoveGroupRequest, SetGroupMembersRequest,
+            UpdateGroupRequest,
+        },
         canvas_node::{
-            BulkCanvasSave, CanvasNodeResponse, CanvasState, ConnectNodesRequest,
-            CreateNodeRequest, DisconnectNodesRequest, ElementLink, NodePlatform, NodeStatus,
-            NodeType, UpdateNodeRequest,
+            BulkCanvasSave, CanvasConnectionInput, CanvasNodeResponse, CanvasState,
+            ConnectNodesRequest, Connection, ConnectionKind, CreateNodeRequest,
+            DisconnectNodesRequest, DuplicateNodesRequest, DuplicateNodesResponse, ElementLink,
+            NodeContentRequest, NodeContentResponse, NodePlatform, NodeSort, NodeStatus,
+            NodeStatusChange, NodeType, TrashedNode, UpdateConnectionRequest, UpdateNodeRequest,
+        },
+        comment::{
+            CommentBodyRequest, CommentResponse, CommentThreadResponse, CreateThreadRequest,
+            MentionedUser, UpdateThreadRequest,
+        },
+        environment::{
+            BrokenBaseReason, BrokenEnvBase, DotenvImportRequest, EffectiveEnvironment,
+            EffectiveVariable, EnvConflict, EnvSource, MissingVariable,
+        },
+        graph::{GraphAnalysis, RelatedNode, ShortestPath},
+        integrity::{
+            BrokenElementLink, BrokenLinkReason, BrokenLinksReport, DanglingElementLink,
+            IntegrityReport,
+        },
+        layout::{LayoutAlgorithm, LayoutRequest, LayoutResponse, NodePosition},
+        lineage::{LineageNode, SetParentRequest},
+        node_payload::{
+            ApiAuth, ApiEndpoint, ApiParam, BillingInterval, CliStep, CliStepKind, DbColumn,
+            DbColumnRef, DbEngine, DbRelation, DbRelationType, DbSchema, DbTable,
+            DeployEnvironment, EnvConfig, HttpMethod, NodePayload, PaymentConfig,
+            PaymentEnvironment, PaymentPlan, PaymentProvider, PaymentSystem,
         },
+        node_lock::{HeldLock, LockRequest, NodeLock},
+        node_revision::{FieldDiff, NodeRevisionSummary, RevisionDiff},
         project::{CreateProjectRequest, Project, UpdateProjectRequest},
-        ui_variation::{SaveVariationsRequest, UiVariation, VariationCategory, VariationPayload},
+        search::SearchResult,
+        secret::{RevealedSecret, SecretReveal},
+        ui_variation::{
+            SaveVariationsRequest, UiVariation, VariationCategory, VariationPayload,
+            VariationSelection,
+        },
         user::{
             AuthResponse, LoginRequest, LogoutRequest, RefreshRequest, RegisterRequest,
             UserResponse,
         },
     },
+    pagination::SortOrder,
     state::AppState,
 };
 
+/// Headroom over the asset size limit for multipart boundaries and headers
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
+
 #[derive(OpenApi)]
 #[openapi(
     info(
@@ -33,36 +77,167 @@ use crate::{
     ),
     paths(
         nodes::list_nodes,
+        nodes::fetch_node_content,
         nodes::create_node,
         nodes::get_node,
         nodes::update_node,
         nodes::delete_node,
         nodes::duplicate_node,
+        nodes::duplicate_nodes,
         nodes::list_connections,
         nodes::connect_nodes,
         nodes::disconnect_nodes,
+        nodes::list_connection_details,
+        nodes::update_connection,
         nodes::remove_element_link,
+        locks::list_locks,
+        node_status::list_status_history,
+        secrets::reveal_secret,
+        secrets::list_secret_reveals,
+        locks::acquire_lock,
+        locks::renew_lock,
+        locks::release_lock,
+        revisions::list_revisions,
+        revisions::diff_revisions,
+        revisions::revert_revision,
+        comments::list_threads,
+        comments::create_thread,
+        comments::update_thread,
+        comments::add_comment,
+        comments::update_comment,
+        comments::delete_comment,
+        assets::upload_asset,
+        assets::list_assets,
+        assets::asset_content,
+        assets::asset_thumbnail,
+        assets::delete_asset,
+        groups::list_groups,
+        groups::create_group,
+        groups::update_group,
+        groups::move_group,
+        groups::set_group_members,
+        groups::delete_group,
+        layout::layout_nodes,
+        graph::analyze_graph,
+        graph::node_ancestors,
+        graph::node_descendants,
+        graph::shortest_path,
+        lineage::lineage_ancestors,
+        lineage::lineage_descendants,
+        lineage::set_parent,
+        lineage::promote_node,
+        environments::effective_environment,
+        environments::import_dotenv,
+        environments::export_dotenv,
+        integrity::repair_project,
+        integrity::broken_element_links,
+        variations::delete_node_variations,
+        variations::select_variation,
+        trash::list_trashed_projects,
+        trash::restore_project,
+        trash::list_trashed_nodes,
+        trash::restore_node,
+        search::search,
     ),
     components(
         schemas(
             CanvasNodeResponse,
+            CanvasGroupResponse,
+            CreateGroupRequest,
+            UpdateGroupRequest,
+            MoveGroupRequest,
+            SetGroupMembersRequest,
             CreateNodeRequest,
             UpdateNodeRequest,
             ConnectNodesRequest,
             DisconnectNodesRequest,
+            DuplicateNodesRequest,
+            DuplicateNodesResponse,
+            Connection,
+            ConnectionKind,
+            UpdateConnectionRequest,
+            CanvasConnectionInput,
             BulkCanvasSave,
             CanvasState,
+            NodeContentRequest,
+            NodeContentResponse,
             NodeType,
             NodeStatus,
             NodePlatform,
+            NodeSort,
+            SortOrder,
             ElementLink,
+            NodePayload,
+            ApiEndpoint,
+            ApiParam,
+            ApiAuth,
+            HttpMethod,
+            CliStep,
+            CliStepKind,
+            DbSchema,
+            DbEngine,
+            DbTable,
+            DbColumn,
+            DbColumnRef,
+            DbRelation,
+            DbRelationType,
+            PaymentConfig,
+            PaymentPlan,
+            PaymentProvider,
+            PaymentEnvironment,
+            PaymentSystem,
+            BillingInterval,
+            EnvConfig,
+            DeployEnvironment,
+            EffectiveEnvironment,
+            EnvSource,
+            EffectiveVariable,
+            EnvConflict,
+            MissingVariable,
+            BrokenEnvBase,
+            BrokenBaseReason,
+            DotenvImportRequest,
+            NodeLock,
+            HeldLock,
+            LockRequest,
+            NodeStatusChange,
+            RevealedSecret,
+            SecretReveal,
+            NodeRevisionSummary,
+            RevisionDiff,
+            FieldDiff,
+            CommentThreadResponse,
+            CommentResponse,
+            MentionedUser,
+            CreateThreadRequest,
+            CommentBodyRequest,
+            UpdateThreadRequest,
+            Asset,
+            AssetUpload,
+            GraphAnalysis,
+            RelatedNode,
+            ShortestPath,
+            LineageNode,
+            SetParentRequest,
+            LayoutRequest,
+            LayoutResponse,
+            LayoutAlgorithm,
+            NodePosition,
+            IntegrityReport,
+            BrokenLinksReport,
+            BrokenElementLink,
+            BrokenLinkReason,
+            DanglingElementLink,
+            TrashedNode,
             Project,
             CreateProjectRequest,
             UpdateProjectRequest,
+            SearchResult,
             UiVariation,
             SaveVariationsRequest,
             VariationPayload,
             VariationCategory,
+            VariationSelection,
             UserResponse,
             AuthResponse,
             LoginRequest,
@@ -115,14 +290,27 @@ pub fn build_router(state: AppState) -> Router {
                 .put(projects::update_project)
                 .delete(projects::delete_project),
         )
+        .route("/api/projects/:id/restore", post(trash::restore_project))
+        .route("/api/projects/:id/trash", get(trash::list_trashed_nodes))
+        .route("/api/trash/projects", get(trash::list_trashed_projects))
         .route(
             "/api/projects/:id/canvas",
-            get(projects::load_canvas).put(projects::save_canvas),
+            get(projects::load_canvas)
+                .put(projects::save_canvas)
+                .layer(DefaultBodyLimit::max(state.cfg.canvas_body_limit_bytes)),
         )
         .route(
             "/api/projects/:id/nodes",
             get(nodes::list_nodes).post(nodes::create_node),
         )
+        .route(
+            "/api/projects/:id/duplicate-nodes",
+            post(nodes::duplicate_nodes),
+        )
+        .route(
+            "/api/projects/:id/node-content",
+            post(nodes::fetch_node_content),
+        )
         .route(
             "/api/projects/:id/nodes/:client_id",
             get(nodes::get_node)
@@ -133,16 +321,155 @@ pub fn build_router(state: AppState) -> Router {
             "/api/projects/:id/nodes/:client_id/duplicate",
             post(nodes::duplicate_node),
         )
+        .route(
+            "/api/projects/:id/nodes/:client_id/restore",
+            post(trash::restore_node),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/lock",
+            post(locks::acquire_lock).delete(locks::release_lock),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/lock/renew",
+            post(locks::renew_lock),
+        )
+        .route("/api/projects/:id/locks", get(locks::list_locks))
+        .route(
+            "/api/projects/:id/nodes/:client_id/status-history",
+            get(node_status::list_status_history),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/secrets/:name/reveal",
+            post(secrets::reveal_secret),
+        )
+        .route(
+            "/api/projects/:id/secret-reveals",
+            get(secrets::list_secret_reveals),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/revisions",
+            get(revisions::list_revisions),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/revisions/diff",
+            get(revisions::diff_revisions),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/revisions/:revision/revert",
+            post(revisions::revert_revision),
+        )
+        .route(
+            "/api/projects/:id/groups",
+            get(groups::list_groups).post(groups::create_group),
+        )
+        .route(
+            "/api/projects/:id/groups/:group_id",
+            patch(groups::update_group).delete(groups::delete_group),
+        )
+        .route("/api/projects/:id/groups/:group_id/move", post(groups::move_group))
+        .route(
+            "/api/projects/:id/groups/:group_id/members",
+            put(groups::set_group_members),
+        )
+        .route(
+            "/api/projects/:id/threads",
+            get(comments::list_threads).post(comments::create_thread),
+        )
+        .route(
+            "/api/projects/:id/threads/:thread_id",
+            patch(comments::update_thread),
+        )
+        .route(
+            "/api/projects/:id/threads/:thread_id/comments",
+            post(comments::add_comment),
+        )
+        .route(
+            "/api/projects/:id/comments/:comment_id",
+            patch(comments::update_comment).delete(comments::delete_comment),
+        )
+        .route(
+            "/api/projects/:id/assets",
+            get(assets::list_assets).post(assets::upload_asset).layer(DefaultBodyLimit::max(
+                state.cfg.asset_max_bytes + MULTIPART_OVERHEAD_BYTES,
+            )),
+        )
+        .route(
+            "/api/projects/:id/assets/:asset_id",
+            delete(assets::delete_asset),
+        )
+        .route(
+            "/api/projects/:id/assets/:asset_id/content",
+            get(assets::asset_content),
+        )
+        .route(
+            "/api/projects/:id/assets/:asset_id/thumbnail",
+            get(assets::asset_thumbnail),
+        )
+        .route("/api/projects/:id/layout", post(layout::layout_nodes))
+        .route("/api/projects/:id/graph/analysis", get(graph::analyze_graph))
+        .route("/api/projects/:id/graph/path", get(graph::shortest_path))
+        .route(
+            "/api/projects/:id/graph/nodes/:client_id/ancestors",
+            get(graph::node_ancestors),
+        )
+        .route(
+            "/api/projects/:id/graph/nodes/:client_id/descendants",
+            get(graph::node_descendants),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/lineage/ancestors",
+            get(lineage::lineage_ancestors),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/lineage/descendants",
+            get(lineage::lineage_descendants),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/parent",
+            put(lineage::set_parent),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/promote",
+            post(lineage::promote_node),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/environment",
+            get(environments::effective_environment),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/env/import",
+            post(environments::import_dotenv),
+        )
+        .route(
+            "/api/projects/:id/nodes/:client_id/env/export",
+            get(environments::export_dotenv),
+        )
         .route(
             "/api/projects/:id/connections",
             get(nodes::list_connections)
                 .post(nodes::connect_nodes)
                 .delete(nodes::disconnect_nodes),
         )
+        .route(
+            "/api/projects/:id/connections/detailed",
+            get(nodes::list_connection_details),
+        )
+        .route(
+            "/api/projects/:id/connections/:connection_id",
+            patch(nodes::update_connection),
+        )
         .route(
             "/api/projects/:id/nodes/:client_id/element-links/:target_id",
             delete(nodes::remove_element_link),
         )
+        .route(
+            "/api/projects/:id/integrity/repair",
+            post(integrity::repair_project),
+        )
+        .route(
+            "/api/projects/:id/integrity/element-links",
+            get(integrity::broken_element_links),
+        )
         .route(
             "/api/projects/:id/variations",
             get(variations::list_variations).post(variations::save_variations),
@@ -151,6 +478,15 @@ pub fn build_router(state: AppState) -> Router {
             "/api/projects/:id/variations/:vid",
             delete(variations::delete_variation),
         )
+        .route(
+            "/api/projects/:id/nodes/:client_id/variations",
+            delete(variations::delete_node_variations),
+        )
+        .route(
+            "/api/projects/:id/variations/:vid/select",
+            post(variations::select_variation),
+        )
+        .route("/api/search", get(search::search))
         .route("/api/ai/complete", post(ai_proxy::complete))
         .route("/api/ai/models", get(ai_proxy::list_models))
         .route(
@@ -0,0 +1,347 @@
+//! Encryption at rest for secret node values: env vars marked secret and the
+//! secret keys of payment configs. Sealed values are stored as
+//! `enc:v1:<base64 nonce ‖ ciphertext>` and only ever leave the API masked.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use rand::RngCore;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::error::{AppError, Result};
+
+/// Stands in for a secret value in responses; sending it back keeps the stored value
+pub const MASK: &str = "••••••••";
+
+const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Payment config fields that are always secret
+pub const PAYMENT_SECRET_FIELDS: [&str; 2] = ["secretKey", "webhookSecret"];
+
+fn derive_key(secret: &str) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(secret.as_bytes());
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hash);
+    key
+}
+
+/// AES-256-GCM encrypt under a key derived from `secret`
+pub fn encrypt(plaintext: &str, secret: &str) -> Result<String> {
+    let cipher_key = derive_key(secret);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&cipher_key));
+
+    let mut nonce_bytes = [0u8; 12];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext.as_bytes())
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Encryption error: {e}")))?;
+
+    let mut combined = nonce_bytes.to_vec();
+    combined.extend_from_slice(&ciphertext);
+
+    Ok(BASE64.encode(combined))
+}
+
+pub fn decrypt(encrypted: &str, secret: &str) -> Result<String> {
+    let data = BASE64
+        .decode(encrypted)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Base64 decode error: {e}")))?;
+
+    if data.len() < 12 {
+        return Err(AppError::Internal(anyhow::anyhow!(
+            "Invalid encrypted data"
+        )));
+    }
+
+    let (nonce_bytes, ciphertext) = data.split_at(12);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let cipher_key = derive_key(secret);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&cipher_key));
+
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("Decryption error: {e}")))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|e| AppError::Internal(anyhow::anyhow!("UTF-8 decode error: {e}")))
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+fn seal(plaintext: &str, secret: &str) -> Result<String> {
+    Ok(format!("{SEALED_PREFIX}{}", encrypt(plaintext, secret)?))
+}
+
+/// Plaintext of a stored value; values written before encryption pass through
+pub fn open(stored: &str, secret: &str) -> Result<String> {
+    match stored.strip_prefix(SEALED_PREFIX) {
+        Some(encrypted) => decrypt(encrypted, secret),
+        None => Ok(stored.to_string()),
+    }
+}
+
+/// Storage form of one incoming secret. The mask keeps what is stored, and an
+/// unchanged value keeps its ciphertext so revisions do not churn.
+fn seal_incoming(
+    field: &str,
+    incoming: &str,
+    stored: Option<&str>,
+    secret: &str,
+) -> Result<String> {
+    if incoming == MASK {
+        let stored = stored.ok_or_else(|| {
+            AppError::Validation(format!("{field}: masked, but no value is stored to keep"))
+        })?;
+        return if is_sealed(stored) {
+            Ok(stored.to_string())
+        } else {
+            seal(stored, secret)
+        };
+    }
+    if is_sealed(incoming) {
+        return Err(AppError::Validation(format!(
+            "{field}: must be plaintext; the server encrypts secrets itself"
+        )));
+    }
+    match stored {
+        Some(s) if is_sealed(s) && open(s, secret)? == incoming => Ok(s.to_string()),
+        _ => seal(incoming, secret),
+    }
+}
+
+/// Storage form of a node's env vars. `marked` names the secret ones; when it
+/// is absent, vars that are already encrypted stay secret.
+pub fn seal_env_vars(
+    incoming: &HashMap<String, String>,
+    marked: Option<&[String]>,
+    stored: &HashMap<String, String>,
+    secret: &str,
+) -> Result<HashMap<String, String>> {
+    if let Some(unknown) = marked
+        .into_iter()
+        .flatten()
+        .find(|k| !incoming.contains_key(*k))
+    {
+        return Err(AppError::Validation(format!(
+            "secretEnvVars: '{unknown}' is not one of the node's env vars"
+        )));
+    }
+
+    incoming
+        .iter()
+        .map(|(key, value)| {
+            let field = format!("envVars.{key}");
+            let previous = stored.get(key).map(String::as_str);
+            let is_secret = match marked {
+                Some(marked) => marked.contains(key),
+                None => previous.is_some_and(is_sealed),
+            };
+            let sealed = seal_incoming(&field, value, previous, secret)?;
+            let value = if is_secret {
+                sealed
+            } else {
+                open(&sealed, secret)?
+            };
+            Ok((key.clone(), value))
+        })
+        .collect()
+}
+
+/// Mask encrypted env vars in place and return their names, sorted
+pub fn mask_env_vars(env_vars: &mut HashMap<String, String>) -> Vec<String> {
+    let mut masked: Vec<String> = env_vars
+        .iter_mut()
+        .filter(|(_, v)| is_sealed(v))
+        .map(|(k, v)| {
+            *v = MASK.to_string();
+            k.clone()
+        })
+        .collect();
+    masked.sort();
+    masked
+}
+
+/// Seal the secret fields of a payment config object in place
+pub fn seal_payment(
+    prefix: &str,
+    config: &mut Value,
+    stored: Option<&Value>,
+    secret: &str,
+) -> Result<()> {
+    for field in PAYMENT_SECRET_FIELDS {
+        let Some(Value::String(incoming)) = config.get(field) else {
+            continue;
+        };
+        if incoming.is_empty() {
+            continue;
+        }
+        let previous = stored.and_then(|s| s.get(field)).and_then(Value::as_str);
+        let sealed = seal_incoming(&format!("{prefix}.{field}"), incoming, previous, secret)?;
+        config[field] = Value::String(sealed);
+    }
+    Ok(())
+}
+
+/// Mask the secret fields of a payment config object; returns whether any
+/// were set. Plaintext left from before encryption is masked too.
+pub fn mask_payment(config: &mut Value) -> bool {
+    let mut masked = false;
+    for field in PAYMENT_SECRET_FIELDS {
+        if let Some(Value::String(value)) = config.get_mut(field) {
+            if !value.is_empty() {
+                *value = MASK.to_string();
+                masked = true;
+            }
+        }
+    }
+    masked
+}
+
+/// The payment editor also keeps its config as JSON text in `generatedCode`.
+/// Text that is not a JSON object, or has no secrets, is stored unchanged.
+pub fn seal_payment_code(code: &str, stored: Option<&str>, secret: &str) -> Result<String> {
+    let Ok(mut config @ Value::Object(_)) = serde_json::from_str::<Value>(code) else {
+        return Ok(code.to_string());
+    };
+    if !PAYMENT_SECRET_FIELDS.iter().any(|f| {
+        config
+            .get(f)
+            .and_then(Value::as_str)
+            .is_some_and(|v| !v.is_empty())
+    }) {
+        return Ok(code.to_string());
+    }
+    let stored: Option<Value> = stored.and_then(|s| serde_json::from_str(s).ok());
+    seal_payment("generatedCode", &mut config, stored.as_ref(), secret)?;
+    Ok(serde_json::to_string_pretty(&config).unwrap_or_default())
+}
+
+/// Seal plaintext secrets in a payment config read back from storage, such
+/// as a revision or a row written before encryption. Unlike client input,
+/// ciphertext here is ours and kept as is. Returns whether anything changed.
+pub fn seal_stored_payment(config: &mut Value, secret: &str) -> Result<bool> {
+    let mut sealed = false;
+    for field in PAYMENT_SECRET_FIELDS {
+        if let Some(Value::String(value)) = config.get_mut(field) {
+            if !value.is_empty() && !is_sealed(value) {
+                *value = seal(value, secret)?;
+                sealed = true;
+            }
+        }
+    }
+    Ok(sealed)
+}
+
+/// `seal_stored_payment` for the config text in `generatedCode`; `None` when
+/// there was no plaintext secret to seal
+pub fn seal_stored_payment_code(code: &str, secret: &str) -> Result<Option<String>> {
+    let Ok(mut config @ Value::Object(_)) = serde_json::from_str::<Value>(code) else {
+        return Ok(None);
+    };
+    if !seal_stored_payment(&mut config, secret)? {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::to_string_pretty(&config).unwrap_or_default()))
+}
+
+/// Mask the secrets in a payment node's `generatedCode`
+pub fn mask_payment_code(code: String) -> String {
+    let Ok(mut config @ Value::Object(_)) = serde_json::from_str::<Value>(&code) else {
+        return code;
+    };
+    if !mask_payment(&mut config) {
+        return code;
+    }
+    serde_json::to_string_pretty(&config).unwrap_or(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const KEY: &str = "test-encryption-secret";
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn env_vars_round_trip_through_mask() {
+        let marked = vec!["STRIPE_SECRET_KEY".to_string()];
+        let incoming = vars(&[("STRIPE_SECRET_KEY", "sk_test_123"), ("PORT", "3000")]);
+        let stored = seal_env_vars(&incoming, Some(&marked), &HashMap::new(), KEY).unwrap();
+        assert!(is_sealed(&stored["STRIPE_SECRET_KEY"]));
+        assert_eq!(stored["PORT"], "3000");
+        assert_eq!(
+            open(&stored["STRIPE_SECRET_KEY"], KEY).unwrap(),
+            "sk_test_123"
+        );
+
+        let mut shown = stored.clone();
+        assert_eq!(mask_env_vars(&mut shown), marked);
+        assert_eq!(shown["STRIPE_SECRET_KEY"], MASK);
+
+        // Sending the response back unchanged, without the marking, keeps everything
+        let again = seal_env_vars(&shown, None, &stored, KEY).unwrap();
+        assert_eq!(again, stored);
+
+        // The same plaintext keeps its ciphertext; unmarking decrypts it
+        let same = seal_env_vars(&incoming, None, &stored, KEY).unwrap();
+        assert_eq!(same["STRIPE_SECRET_KEY"], stored["STRIPE_SECRET_KEY"]);
+        let unmarked = seal_env_vars(&shown, Some(&[]), &stored, KEY).unwrap();
+        assert_eq!(unmarked["STRIPE_SECRET_KEY"], "sk_test_123");
+    }
+
+    #[test]
+    fn rejects_masks_without_a_stored_value_and_client_ciphertext() {
+        let masked = vars(&[("TOKEN", MASK)]);
+        assert!(seal_env_vars(&masked, None, &HashMap::new(), KEY).is_err());
+
+        let forged = vars(&[("TOKEN", "enc:v1:AAAA")]);
+        assert!(seal_env_vars(&forged, None, &HashMap::new(), KEY).is_err());
+
+        let unknown = vec!["MISSING".to_string()];
+        assert!(seal_env_vars(&HashMap::new(), Some(&unknown), &HashMap::new(), KEY).is_err());
+    }
+
+    #[test]
+    fn payment_secrets_are_sealed_in_payload_and_code() {
+        let mut payload =
+            json!({ "provider": "Stripe", "secretKey": "sk_live", "publicKey": "pk" });
+        seal_payment("payload", &mut payload, None, KEY).unwrap();
+        let sealed = payload["secretKey"].as_str().unwrap().to_string();
+        assert!(is_sealed(&sealed));
+        assert_eq!(payload["publicKey"], "pk");
+
+        let mut shown = payload.clone();
+        assert!(mask_payment(&mut shown));
+        assert_eq!(shown["secretKey"], MASK);
+        seal_payment("payload", &mut shown, Some(&payload), KEY).unwrap();
+        assert_eq!(shown["secretKey"], sealed.as_str());
+
+        let code = r#"{"provider":"Stripe","webhookSecret":"whsec_1"}"#;
+        let stored = seal_payment_code(code, None, KEY).unwrap();
+        assert!(!stored.contains("whsec_1"));
+        assert!(mask_payment_code(stored).contains(MASK));
+        assert_eq!(seal_payment_code("<div/>", None, KEY).unwrap(), "<div/>");
+
+        // Stored ciphertext is kept, plaintext left from before encryption is sealed
+        let legacy = format!(r#"{{"secretKey":"sk_old","webhookSecret":"{sealed}"}}"#);
+        let resealed = seal_stored_payment_code(&legacy, KEY).unwrap().unwrap();
+        assert!(!resealed.contains("sk_old") && resealed.contains(&sealed));
+        assert_eq!(seal_stored_payment_code(&resealed, KEY).unwrap(), None);
+    }
+}
@@ -5,6 +5,7 @@ use std::time::Instant;
 use tokio::sync::RwLock;
 
 use crate::config::Config;
+use crate::storage::{self, AssetStorage};
 
 #[derive(Clone)]
 pub struct AppState {
@@ -12,6 +13,7 @@ pub struct AppState {
     pub cfg: Config,
     pub http: Client,
     pub model_cache: Arc<RwLock<Option<ModelCache>>>,
+    pub storage: Arc<dyn AssetStorage>,
 }
 
 #[derive(Clone)]
@@ -28,11 +30,14 @@ impl AppState {
             .build()
             .expect("Failed to build reqwest client");
 
+        let storage = storage::from_config(&cfg.asset_storage, http.clone());
+
         Self {
             db,
             cfg,
             http,
             model_cache: Arc::new(RwLock::new(None)),
+            storage,
         }
     }
 }
@@ -0,0 +1,78 @@
+use axum::{async_trait, body::Bytes};
+use std::{io::ErrorKind, path::PathBuf};
+
+use super::AssetStorage;
+
+/// Stores each object as a file under `root`
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> anyhow::Result<PathBuf> {
+        if key.is_empty() || key.split('/').any(|part| part.is_empty() || part == "..") {
+            anyhow::bail!("Invalid storage key '{key}'");
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl AssetStorage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Bytes, _content_type: &str) -> anyhow::Result<()> {
+        let path = self.path(key)?;
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        // Write beside the target and rename so readers never see a partial file
+        let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        tokio::fs::write(&tmp, &bytes).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Bytes>> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(bytes) => Ok(Some(bytes.into())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trips_objects() {
+        let root = std::env::temp_dir().join(format!("assets-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(root.clone());
+
+        storage
+            .put("assets/ab/cd", Bytes::from_static(b"hello"), "text/plain")
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get("assets/ab/cd").await.unwrap().as_deref(),
+            Some(&b"hello"[..])
+        );
+        storage.delete("assets/ab/cd").await.unwrap();
+        storage.delete("assets/ab/cd").await.unwrap();
+        assert!(storage.get("assets/ab/cd").await.unwrap().is_none());
+        assert!(storage.get("../etc/passwd").await.is_err());
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+//! Object storage for uploaded assets
+
+pub mod local;
+pub mod s3;
+
+use axum::{async_trait, body::Bytes};
+use reqwest::Client;
+use std::sync::Arc;
+
+use crate::config::AssetStorageConfig;
+
+/// A flat key/value blob store. Keys are `/`-separated and never contain `..`.
+#[async_trait]
+pub trait AssetStorage: Send + Sync {
+    async fn put(&self, key: &str, bytes: Bytes, content_type: &str) -> anyhow::Result<()>;
+
+    /// `None` when no object exists under `key`
+    async fn get(&self, key: &str) -> anyhow::Result<Option<Bytes>>;
+
+    /// Deleting a missing object is not an error
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// Build the configured backend
+pub fn from_config(cfg: &AssetStorageConfig, http: Client) -> Arc<dyn AssetStorage> {
+    match cfg {
+        AssetStorageConfig::Local { root } => Arc::new(local::LocalStorage::new(root.clone())),
+        AssetStorageConfig::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        } => Arc::new(s3::S3Storage {
+            http,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.clone(),
+            region: region.clone(),
+            access_key: access_key.clone(),
+            secret_key: secret_key.clone(),
+        }),
+    }
+}
@@ -0,0 +1,91 @@
+//! Database-backed test fixtures. Tests using them are `#[ignore]`d and run
+//! against `TEST_DATABASE_URL` with `cargo test -- --ignored`.
+
+use sqlx::postgres::PgPoolOptions;
+use uuid::Uuid;
+
+use crate::{
+    config::{AssetStorageConfig, Config},
+    middleware::auth::AuthUser,
+    state::AppState,
+};
+
+/// A migrated database with one user owning one empty project
+pub struct TestProject {
+    pub state: AppState,
+    pub user_id: Uuid,
+    pub project_id: Uuid,
+}
+
+impl TestProject {
+    pub async fn new() -> Self {
+        let database_url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL");
+        let pool = PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&database_url)
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let user_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO users (email, display_name) VALUES ($1, 'test') RETURNING id",
+        )
+        .bind(format!("test-{}@example.com", Uuid::new_v4()))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let project_id: Uuid = sqlx::query_scalar(
+            "INSERT INTO projects (user_id, name) VALUES ($1, 'test') RETURNING id",
+        )
+        .bind(user_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        let cfg = Config {
+            database_url,
+            jwt_secret: "test".into(),
+            jwt_expiry_secs: 900,
+            refresh_expiry_secs: 3600,
+            host: "127.0.0.1".into(),
+            port: 0,
+            db_max_connections: 2,
+            openrouter_fallback_key: None,
+            openrouter_base_url: "http://localhost".into(),
+            api_key_encryption_secret: "test".into(),
+            trash_retention_days: 30,
+            trash_purge_interval_secs: 3600,
+            canvas_body_limit_bytes: 52_428_800,
+            node_lock_default_ttl_secs: 60,
+            node_lock_max_ttl_secs: 600,
+            node_generating_timeout_secs: 900,
+            node_running_timeout_secs: 3600,
+            status_sweep_interval_secs: 60,
+            asset_max_bytes: 10_485_760,
+            asset_storage: AssetStorageConfig::Local {
+                root: std::env::temp_dir().join("canvas-assets"),
+            },
+        };
+
+        Self {
+            state: AppState::new(pool, cfg),
+            user_id,
+            project_id,
+        }
+    }
+
+    pub fn owner(&self) -> AuthUser {
+        AuthUser {
+            user_id: self.user_id,
+        }
+    }
+
+    /// Remove the user and, through the cascade, everything they own
+    pub async fn cleanup(self) {
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(self.user_id)
+            .execute(&self.state.db)
+            .await
+            .unwrap();
+    }
+}